@@ -0,0 +1,87 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde::Serialize;
+use serde_json::Value;
+use tinue_finder::compact::decode_tinue_tree;
+use tinue_finder::db;
+use tinue_finder::serialize::tinue_to_value;
+use tinue_finder::{IDDFSResult, TinueFinderError};
+
+use super::output::{log_format_arg, output_arg, Output};
+use super::{board_size_arg, database_arg, get_optional_arg_number};
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("expand")
+        .about("Decodes the trees stored with `scan --compact-tree` and writes them as JSON")
+        .arg(
+            database_arg()
+                .help("Path of the database with the `tinues` table")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("id")
+                .long("id")
+                .takes_value(true)
+                .help("Expands only the tinue with this id")
+                .required(false),
+        )
+        .arg(board_size_arg().required(false))
+        .arg(output_arg())
+        .arg(log_format_arg())
+}
+
+/// A decoded tree, written as a JSON line
+#[derive(Serialize)]
+struct ExpandRecord {
+    id: u32,
+    gameid: u32,
+    source: String,
+    size: u32,
+    depth: u32,
+    /// All Tinues as a `VersionedTinue` of kind `options`
+    tinue: Value,
+}
+
+/// Writes the decoded tree of every tinue of `--db` that has one
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let db_path = matches.value_of("database").unwrap();
+    let id = get_optional_arg_number(matches, "id")?;
+    let board_size = get_optional_arg_number(matches, "board_size")?;
+    let output = Output::from_matches(matches)?;
+
+    let conn = db::open_read_only(db_path)?;
+
+    let mut expanded = 0;
+    for row in db::select_tinues(&conn, board_size)? {
+        if id.map_or(false, |id| id != row.id) {
+            continue;
+        }
+        let tree = match &row.tinue.tinue_tree {
+            Some(tree) => tree,
+            None => continue,
+        };
+        let result = match decode_tinue_tree(tree) {
+            Ok(result) => result,
+            Err(err) => {
+                output.warn(&format!("Skipping tinue #{}: {}", row.id, err));
+                continue;
+            }
+        };
+        let tinue = IDDFSResult {
+            depth: row.tinue.tinue_depth,
+            result,
+        };
+        output.record(&ExpandRecord {
+            id: row.id,
+            gameid: row.tinue.gameid,
+            source: row.tinue.game_source().to_string(),
+            size: row.tinue.size,
+            depth: row.tinue.tinue_depth,
+            tinue: tinue_to_value(Some(&tinue), false)?,
+        })?;
+        expanded += 1;
+    }
+    output.flush()?;
+    output.info(&format!("Expanded {} tinue trees", expanded));
+
+    Ok(())
+}
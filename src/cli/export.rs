@@ -0,0 +1,237 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tinue_finder::compact::decode_tinue_tree;
+use tinue_finder::db::{self, StoredTinueRow};
+use tinue_finder::game::{do_it_sized, puzzle_moves_sized, Game};
+use tinue_finder::ptn::{annotated_ptn, tinue_line, PtnPly};
+use tinue_finder::serialize::{options_to_tinuemoves, parse_tinue_json};
+use tinue_finder::{SearchLimits, Solver, TinueFinderError, TinueMove, TinueSolution};
+
+use super::output::{log_format_arg, Output};
+use super::{
+    board_size_arg, database_arg, get_optional_arg_number, get_search_limits, load_games,
+    max_nodes_arg, max_time_arg, ptn_arg,
+};
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("export")
+        .about("Writes every stored tinue to its own file")
+        .arg(
+            database_arg()
+                .help("Path of the database with the `tinues` table. For `--format ptn` games are read from its `games` table unless `--ptn` is given")
+                .required(true),
+        )
+        .arg(ptn_arg())
+        .arg(
+            Arg::with_name("out")
+                .short("o")
+                .long("out")
+                .takes_value(true)
+                .help("Directory the files are written to, it is created if it does not exist")
+                .required(true),
+        )
+        .arg(board_size_arg().required(false))
+        .arg(
+            Arg::with_name("duplicates")
+                .long("duplicates")
+                .help("Also exports tinues whose position was already found in another game")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("min_depth")
+                .long("min-depth")
+                .takes_value(true)
+                .help("Exports only tinues of at least this many plies")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["json", "ptn"])
+                .help("`json` writes the stored tinue, `ptn` writes the game up to the puzzle followed by the Tinue, with every defense as a variation")
+                .required(false)
+                .default_value("json"),
+        )
+        .arg(max_nodes_arg().help("For `--format ptn`, gives up on a tinue that has to be searched again after this many positions"))
+        .arg(max_time_arg().help("For `--format ptn`, gives up on a tinue that has to be searched again after this many seconds"))
+        .arg(log_format_arg())
+}
+
+/// The stored tinue of `row` as JSON, tinues of older versions are converted to the current format
+fn tinue_json(row: &StoredTinueRow) -> Result<String, TinueFinderError> {
+    let tinue = &row.tinue;
+    let puzzle = json!({
+        "id": row.id,
+        "gameid": tinue.gameid,
+        "source": tinue.game_source(),
+        "size": tinue.size,
+        "movesToUndo": tinue.plies_to_undo,
+        "depth": tinue.tinue_depth,
+        "tinue": parse_tinue_json(&tinue.tinue)?,
+        "duplicateOf": row.duplicate_of,
+        "tps": tinue.tps,
+        "sideToMove": tinue.side_to_move,
+        "ply": tinue.ply,
+        "playedMove": tinue.played_move,
+    });
+    Ok(serde_json::to_string_pretty(&puzzle)?)
+}
+
+/// Every **Road to Tinue** of `row`, from its `tinue_tree` or its stored options.
+///
+/// A tinue stored as a line has only the longest defense, so its position is searched again within `limits`.
+/// Returns `None` if the search finds no Tinue of the stored depth.
+fn tinue_moves(
+    row: &StoredTinueRow,
+    game: &Game,
+    limits: &SearchLimits,
+) -> Result<Option<Vec<TinueMove>>, TinueFinderError> {
+    let tinue = &row.tinue;
+    if let Some(tree) = &tinue.tinue_tree {
+        return Ok(Some(decode_tinue_tree(tree)?));
+    }
+    if let Some(stored) = parse_tinue_json(&tinue.tinue)? {
+        if let TinueSolution::Options { options } = stored.tinue {
+            return Ok(Some(options_to_tinuemoves(&options)));
+        }
+    }
+    let (result, _stats) = do_it_sized(
+        tinue.size,
+        &game.moves,
+        tinue.plies_to_undo,
+        tinue.tinue_depth,
+        tinue.tinue_depth,
+        false,
+        Solver::Iddfs,
+        limits,
+    )?;
+    Ok(result.map(|result| result.result))
+}
+
+/// The moves of `game` up to the puzzle of `row`, followed by the annotated Tinue.
+///
+/// Returns `None` if the Tinue had to be searched again and was not found, see `tinue_moves`.
+fn tinue_ptn(
+    row: &StoredTinueRow,
+    game: &Game,
+    limits: &SearchLimits,
+) -> Result<Option<String>, TinueFinderError> {
+    let tinue = &row.tinue;
+    let game_moves = puzzle_moves_sized(tinue.size, &game.moves, tinue.plies_to_undo)?;
+    let result = match tinue_moves(row, game, limits)? {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+
+    let side_to_move = if game_moves.len() % 2 == 0 {
+        "White"
+    } else {
+        "Black"
+    };
+    let mut plies = game_moves.into_iter().map(PtnPly::new).collect::<Vec<_>>();
+    if let Some(last) = plies.last_mut() {
+        last.comment = Some(format!(
+            "{} to move, Tinue in {} plies",
+            side_to_move, tinue.tinue_depth
+        ));
+    }
+    plies.extend(tinue_line(&result));
+
+    let mut tags = vec![];
+    if let Some(player) = &game.player_white {
+        tags.push(("Player1", player.clone()));
+    }
+    if let Some(player) = &game.player_black {
+        tags.push(("Player2", player.clone()));
+    }
+    tags.push(("Size", tinue.size.to_string()));
+    if let Some(komi) = game.komi {
+        tags.push(("Komi", komi.to_string()));
+    }
+    tags.push(("Id", tinue.gameid.to_string()));
+    tags.push(("TinueDepth", tinue.tinue_depth.to_string()));
+    tags.push(("MovesToUndo", tinue.plies_to_undo.to_string()));
+    Ok(Some(annotated_ptn(&tags, &plies)))
+}
+
+/// Writes the tinues of `--db` as `<id>.json` or `.ptn` files to `--out`.
+///
+/// The files are named by the id of the row, game ids may repeat across sources and scans.
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let db_path = matches.value_of("database").unwrap();
+    let out_dir = Path::new(matches.value_of("out").unwrap());
+    let board_size = get_optional_arg_number(matches, "board_size")?;
+    let min_depth = get_optional_arg_number(matches, "min_depth")?.unwrap_or(0);
+    let duplicates = matches.occurrences_of("duplicates") > 0;
+    let ptn = matches.value_of("format") == Some("ptn");
+    let limits = get_search_limits(matches)?;
+    // Only for the progress messages, the tinues are written to files
+    let output = Output::from_matches(matches)?;
+
+    let conn = db::open_read_only(db_path)?;
+    fs::create_dir_all(out_dir)?;
+
+    let rows = db::select_tinues(&conn, board_size)?
+        .into_iter()
+        .filter(|row| {
+            row.tinue.tinue_depth >= min_depth && (row.duplicate_of.is_none() || duplicates)
+        })
+        .collect::<Vec<_>>();
+    let games = match ptn {
+        true => load_games(matches, &rows)?,
+        false => HashMap::new(),
+    };
+
+    let mut exported = 0;
+    for row in rows.iter() {
+        let tinue = &row.tinue;
+        let (text, extension) = match ptn {
+            false => (tinue_json(row)?, "json"),
+            true => {
+                let game = games
+                    .get(&tinue.gameid)
+                    .filter(|game| game.source == tinue.game_source());
+                let game = match game {
+                    Some(game) => game,
+                    None => {
+                        output.warn(&format!(
+                            "Skipping tinue #{}: game #{} of {} not found",
+                            row.id,
+                            tinue.gameid,
+                            tinue.game_source()
+                        ));
+                        continue;
+                    }
+                };
+                match tinue_ptn(row, game, &limits) {
+                    Ok(Some(text)) => (text, "ptn"),
+                    Ok(None) => {
+                        output.warn(&format!(
+                            "Skipping tinue #{}: no Tinue of {} plies found, check it with `verify`",
+                            row.id, tinue.tinue_depth
+                        ));
+                        continue;
+                    }
+                    Err(err) => {
+                        output.warn(&format!("Skipping tinue #{}: {}", row.id, err));
+                        continue;
+                    }
+                }
+            }
+        };
+        let path = out_dir.join(format!("{}.{}", row.id, extension));
+        fs::write(path, text)?;
+        exported += 1;
+    }
+    output.info(&format!(
+        "Exported {} tinues to {}",
+        exported,
+        out_dir.display()
+    ));
+
+    Ok(())
+}
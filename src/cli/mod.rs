@@ -0,0 +1,169 @@
+use clap::{Arg, ArgMatches};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Duration;
+use tinue_finder::db::StoredTinueRow;
+use tinue_finder::game::Game;
+use tinue_finder::game_source::{open_ptn_source, GameFilter, GameSource, SqliteGameSource};
+use tinue_finder::{SearchLimits, Solver, TinueFinderError};
+
+pub mod expand;
+pub mod export;
+pub mod output;
+pub mod scan;
+pub mod schema;
+pub mod solve;
+pub mod stats;
+pub mod verify;
+pub mod writer;
+
+/// Parses the value of `arg_name` as a number
+pub fn get_arg_number(matches: &ArgMatches, arg_name: &str) -> Result<u32, TinueFinderError> {
+    let value = matches.value_of(arg_name).unwrap_or_default();
+    value.parse::<u32>().map_err(|_| {
+        TinueFinderError::InvalidArgument(format!(
+            "'{}' is not a valid number for {}",
+            value, arg_name
+        ))
+    })
+}
+
+/// Parses the value of `arg_name` as a number if it was given
+pub fn get_optional_arg_number(
+    matches: &ArgMatches,
+    arg_name: &str,
+) -> Result<Option<u32>, TinueFinderError> {
+    match matches.value_of(arg_name) {
+        Some(_) => get_arg_number(matches, arg_name).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Parses `--max-depth`, which has to be odd: an even depth would mean that the opponent does the final ply
+pub fn get_max_depth(matches: &ArgMatches) -> Result<u32, TinueFinderError> {
+    let max_depth = get_arg_number(matches, "max_depth")?;
+    if max_depth % 2 != 1 {
+        return Err(TinueFinderError::InvalidArgument("max_depth must be an odd number as it represents the number of plies looked ahead. An even number would mean that your opponent does the final ply".to_string()));
+    }
+    Ok(max_depth)
+}
+
+pub fn get_solver(matches: &ArgMatches) -> Result<Solver, TinueFinderError> {
+    Solver::from_str(matches.value_of("solver").unwrap()).map_err(TinueFinderError::InvalidArgument)
+}
+
+pub fn database_arg() -> Arg<'static, 'static> {
+    Arg::with_name("database")
+        .long("db")
+        .takes_value(true)
+        .help("Path of the database")
+}
+
+pub fn ptn_arg() -> Arg<'static, 'static> {
+    Arg::with_name("ptn")
+        .long("ptn")
+        .takes_value(true)
+        .help("Reads the games from a PTN file, a directory of PTN files or a zip or tar archive of them instead of the database")
+        .required(false)
+}
+
+pub fn board_size_arg() -> Arg<'static, 'static> {
+    Arg::with_name("board_size")
+        .short("n")
+        .long("board-size")
+        .takes_value(true)
+        .help("Checks only games of this board size")
+}
+
+pub fn solver_arg() -> Arg<'static, 'static> {
+    Arg::with_name("solver")
+        .long("solver")
+        .takes_value(true)
+        .possible_values(&["iddfs", "pns"])
+        .help("Algorithm used to search for tinues. `pns` (proof-number search) finds long tinues quicker but does not guarantee the shortest one")
+        .required(false)
+        .default_value("iddfs")
+}
+
+pub fn max_nodes_arg() -> Arg<'static, 'static> {
+    Arg::with_name("max_nodes")
+        .long("max-nodes")
+        .takes_value(true)
+        .help("Gives up on a position after searching this many positions")
+        .required(false)
+}
+
+pub fn max_time_arg() -> Arg<'static, 'static> {
+    Arg::with_name("max_time")
+        .long("max-time")
+        .takes_value(true)
+        .help("Gives up on a position after searching for this many seconds")
+        .required(false)
+}
+
+/// Reads the limits of `max_nodes_arg`, `max_time_arg` and `max_tree_nodes_arg`, those the command doesn't have are unlimited
+pub fn get_search_limits(matches: &ArgMatches) -> Result<SearchLimits, TinueFinderError> {
+    Ok(SearchLimits {
+        max_nodes: get_optional_arg_number(matches, "max_nodes")?.map(u64::from),
+        max_time: get_optional_arg_number(matches, "max_time")?
+            .map(|secs| Duration::from_secs(secs.into())),
+        max_tree_nodes: get_optional_arg_number(matches, "max_tree_nodes")?.map(u64::from),
+        ..SearchLimits::unlimited()
+    })
+}
+
+pub fn max_tree_nodes_arg() -> Arg<'static, 'static> {
+    Arg::with_name("max_tree_nodes")
+        .long("max-tree-nodes")
+        .takes_value(true)
+        .help("Gives up on a position when the tree of `--solver pns` has this many nodes, which take about 100 bytes each")
+        .required(false)
+        .default_value("10000000")
+}
+
+/// Returns the games given with `--ptn`, or the `games` table of `--db` otherwise
+pub fn open_game_source(matches: &ArgMatches) -> Result<Box<dyn GameSource>, TinueFinderError> {
+    match (matches.value_of("ptn"), matches.value_of("database")) {
+        (Some(ptn_path), _) => Ok(open_ptn_source(ptn_path)),
+        (None, Some(db_path)) => Ok(Box::new(SqliteGameSource::open(db_path)?)),
+        (None, None) => Err(TinueFinderError::InvalidArgument(
+            "either --db or --ptn is required to read games".to_string(),
+        )),
+    }
+}
+
+/// Reads the games of `rows` from the source of `open_game_source`, by game id.
+///
+/// The games are streamed and only those of `rows` are kept. Rows of other sources and games
+/// that cannot be read are left out.
+pub fn load_games(
+    matches: &ArgMatches,
+    rows: &[StoredTinueRow],
+) -> Result<HashMap<u32, Game>, TinueFinderError> {
+    let source = open_game_source(matches)?;
+    let source_name = source.name();
+    let mut wanted: BTreeMap<u32, HashSet<u32>> = BTreeMap::new();
+    for row in rows
+        .iter()
+        .filter(|row| row.tinue.game_source() == source_name)
+    {
+        wanted
+            .entry(row.tinue.size)
+            .or_default()
+            .insert(row.tinue.gameid);
+    }
+
+    let mut games = HashMap::new();
+    for (size, ids) in wanted {
+        let filter = GameFilter {
+            board_size: size,
+            min_game_id: ids.iter().copied().min().unwrap_or_default(),
+        };
+        for game in source.road_wins(&filter)?.filter_map(Result::ok) {
+            if ids.contains(&game.id) {
+                games.insert(game.id, game);
+            }
+        }
+    }
+    Ok(games)
+}
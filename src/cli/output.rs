@@ -0,0 +1,108 @@
+use clap::{Arg, ArgMatches};
+use serde::Serialize;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use tinue_finder::TinueFinderError;
+
+/// How progress messages are written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line with `level` and `message`
+    Json,
+}
+
+/// Writes result records as JSON lines to the sink chosen with `--output`,
+/// and progress messages to stderr in the format chosen with `--log-format`.
+///
+/// Can be shared between threads, every record is written as a whole line.
+/// Clones write to the same sink.
+#[derive(Clone)]
+pub struct Output {
+    /// `None` if the records are discarded
+    sink: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    log_format: LogFormat,
+}
+
+pub fn output_arg() -> Arg<'static, 'static> {
+    Arg::with_name("output")
+        .long("output")
+        .takes_value(true)
+        .help("Where to write the results as JSON lines: `-` for stdout, `none` to discard them, or the path of a file")
+        .required(false)
+        .default_value("-")
+}
+
+pub fn log_format_arg() -> Arg<'static, 'static> {
+    Arg::with_name("log_format")
+        .long("log-format")
+        .takes_value(true)
+        .possible_values(&["text", "json"])
+        .help("Format of the progress messages written to stderr")
+        .required(false)
+        .default_value("text")
+}
+
+impl Output {
+    /// Opens the sink of `--output` and reads `--log-format`
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, TinueFinderError> {
+        let sink: Option<Box<dyn Write + Send>> = match matches.value_of("output") {
+            None | Some("-") => Some(Box::new(io::stdout())),
+            Some("none") => None,
+            Some(path) => Some(Box::new(BufWriter::new(File::create(path)?))),
+        };
+        let log_format = match matches.value_of("log_format") {
+            Some("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        };
+        Ok(Output {
+            sink: sink.map(|sink| Arc::new(Mutex::new(sink))),
+            log_format,
+        })
+    }
+
+    /// Writes `record` as a single JSON line
+    pub fn record<T: Serialize>(&self, record: &T) -> Result<(), TinueFinderError> {
+        if let Some(sink) = &self.sink {
+            let line = serde_json::to_string(record)?;
+            let mut sink = sink.lock().unwrap();
+            writeln!(sink, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `text` unchanged, for the outputs that are not JSON lines (e.g. PTN)
+    pub fn text(&self, text: &str) -> Result<(), TinueFinderError> {
+        if let Some(sink) = &self.sink {
+            let mut sink = sink.lock().unwrap();
+            write!(sink, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    pub fn info(&self, message: &str) {
+        self.log("info", message);
+    }
+
+    pub fn warn(&self, message: &str) {
+        self.log("warn", message);
+    }
+
+    fn log(&self, level: &str, message: &str) {
+        match self.log_format {
+            LogFormat::Text if level == "info" => eprintln!("{}", message),
+            LogFormat::Text => eprintln!("{}: {}", level, message),
+            LogFormat::Json => eprintln!("{}", json!({ "level": level, "message": message })),
+        }
+    }
+
+    /// Writes the records that are still buffered, e.g. before the program exits
+    pub fn flush(&self) -> Result<(), TinueFinderError> {
+        if let Some(sink) = &self.sink {
+            sink.lock().unwrap().flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,878 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rayon::current_thread_index;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use tinue_finder::compact::encode_tinue_tree;
+use tinue_finder::db::{
+    self, DefensePuzzleRow, MissedTinueRow, PlyTinueRow, ScanOutcome, ScanRunRow, ScannedGameRow,
+    TinueGameRow,
+};
+use tinue_finder::game::{
+    do_it_sized, find_defense_sized, puzzle_start_sized, scan_plies_sized, Game,
+};
+use tinue_finder::game_source::{GameFilter, GameSource};
+use tinue_finder::serialize::{color_name, tinue_to_json, tinue_to_value};
+use tinue_finder::symmetry::canonical_key;
+use tinue_finder::{Mov, SearchAborted, SearchLimits, SearchStats, Solver, TinueFinderError};
+
+use super::output::{log_format_arg, output_arg, Output};
+use super::writer::{batch_secs_arg, batch_size_arg, BatchSettings, DbWriter};
+use super::{
+    board_size_arg, database_arg, get_arg_number, get_max_depth, get_optional_arg_number,
+    get_search_limits, get_solver, max_nodes_arg, max_time_arg, max_tree_nodes_arg,
+    open_game_source, ptn_arg, solver_arg,
+};
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("scan")
+        .about("Checks a database of Tak games for Tinues and writes them in a new table")
+        .arg(
+            database_arg()
+                .help("Path of the database. Games are read from its `games` table unless `--ptn` is given, tinues are written to it")
+                .required_unless("ptn"),
+        )
+        .arg(ptn_arg())
+        .arg(board_size_arg().required(true))
+        .arg(
+            Arg::with_name("start_id")
+                .short("s")
+                .long("start-id")
+                .takes_value(true)
+                .help("Skips games with a smaller ID, e.g. old games that were played under different rules. Pass `0` to scan every game, e.g. of `--ptn`")
+                .required(false)
+                .default_value("8000"),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .help("Skips the games that were already scanned with the same board size, mode, `--undo`, `--max-depth` and `--multi-tinue` (see the `scanned_games` table)")
+                .required(false)
+                .conflicts_with("test"),
+        )
+        .arg(
+            Arg::with_name("plies_to_undo")
+                .short("u")
+                .long("undo")
+                .takes_value(true)
+                .help("Number of plies to undo from the end position")
+                .required(false)
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("max_depth")
+                .short("d")
+                .long("max-depth")
+                .takes_value(true)
+                .help("Maximum depth/length of a Tinue in plies (must be odd)")
+                .required(false)
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .long("threads")
+                .takes_value(true)
+                .help("Number of threads to use to find puzzles concurrently")
+                .required(false)
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("multi_tinue")
+                .short("m")
+                .long("multi-tinue")
+                .help("Searches for all available tinues and how to handle all possible opponent replies. Increases computation time and output data massively.")
+                .required(false)
+        )
+        .arg(
+            Arg::with_name("compact_tree")
+                .long("compact-tree")
+                .help("Stores the complete tree of `--multi-tinue` in the compact binary encoding in the `tinue_tree` column, the `tinue` column only gets the longest line. Use the `expand` subcommand to read it.")
+                .required(false)
+                .requires("multi_tinue")
+                .conflicts_with_all(&["every_ply", "defense"])
+        )
+        .arg(
+            Arg::with_name("every_ply")
+                .short("a")
+                .long("every-ply")
+                .help("Searches every position of the games instead of only the one `--undo` plies before the end. Finds the moment a game was lost and tinues that were missed.")
+                .required(false)
+        )
+        .arg(
+            Arg::with_name("defense")
+                .long("defense")
+                .help("Searches for defense puzzles instead: positions `--undo` plies before the end in which only a few replies escape a tinue of `--max-depth` plies")
+                .required(false)
+                .conflicts_with("every_ply")
+        )
+        .arg(
+            Arg::with_name("max_saving_moves")
+                .long("max-saving-moves")
+                .takes_value(true)
+                .help("Maximum number of replies that may escape the tinue in a defense puzzle")
+                .required(false)
+                .default_value("1"),
+        )
+        .arg(solver_arg())
+        .arg(max_nodes_arg().help("Gives up on a game after searching this many positions. The game is recorded as aborted and scanned again by `--resume`"))
+        .arg(max_time_arg().help("Gives up on a game after searching for this many seconds. The game is recorded as aborted and scanned again by `--resume`"))
+        .arg(max_tree_nodes_arg())
+        .arg(
+            Arg::with_name("test")
+                .short("t")
+                .long("test")
+                .help("Only logs the output, does not write to the database")
+                .required(false)
+        )
+        .arg(batch_size_arg())
+        .arg(batch_secs_arg())
+        .arg(output_arg())
+        .arg(log_format_arg())
+}
+
+/// Searches `game` for a Tinue, skipping the depths below `min_depth`
+fn handle_game(
+    game: &Game,
+    min_depth: u32,
+    max_depth: u32,
+    plies_to_undo: u32,
+    find_only_one_tinue: bool,
+    compact_tree: bool,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<GameScan, TinueFinderError> {
+    let timer = Instant::now();
+
+    let (moves, stats) = do_it_sized(
+        game.size,
+        &game.moves,
+        plies_to_undo,
+        min_depth,
+        max_depth,
+        find_only_one_tinue,
+        solver,
+        limits,
+    )?;
+    let actual_depth = moves.as_ref().map(|x| x.depth).unwrap_or(0);
+
+    // The complete tree is stored encoded instead
+    let tinue = tinue_to_value(moves.as_ref(), find_only_one_tinue || compact_tree)?;
+
+    let record = ScanRecord::Tinue(TinueRecord {
+        id: game.id,
+        size: game.size,
+        result: game.result.clone(),
+        min_depth,
+        max_depth,
+        depth: actual_depth,
+        moves_to_undo: plies_to_undo,
+        time_ms: timer.elapsed().as_millis() as u64,
+        stats: stats.clone(),
+        tinue: tinue.clone(),
+    });
+
+    let row = match actual_depth {
+        0 | 1 => None, // Ignore no wins and  immediate wins
+        _ => {
+            let start = puzzle_start_sized(game.size, &game.moves, plies_to_undo)?;
+            Some(TinueGameRow {
+                plies_to_undo,
+                gameid: game.id,
+                source: Some(game.source.clone()),
+                tinue: tinue.to_string(),
+                size: game.size,
+                tinue_depth: actual_depth,
+                canonical_key: Some(canonical_key(&start.tps)?),
+                side_to_move: Some(color_name(start.side_to_move).to_string()),
+                ply: Some(start.ply),
+                played_move: start.played_move,
+                tps: Some(start.tps),
+                tinue_tree: match (&moves, compact_tree) {
+                    (Some(moves), true) => Some(encode_tinue_tree(&moves.result)?),
+                    _ => None,
+                },
+            })
+        }
+    };
+    Ok(GameScan {
+        results: GameResults::Tinue {
+            depth: actual_depth,
+            row,
+        },
+        stats,
+        record,
+    })
+}
+
+/// Searches every position of `game` for the positions where the side to move had a Tinue,
+/// as well as those where the Tinue was missed
+fn handle_game_plies(
+    game: &Game,
+    max_depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<GameScan, TinueFinderError> {
+    let timer = Instant::now();
+
+    let (ply_results, stats) = scan_plies_sized(
+        game.size,
+        &game.moves,
+        max_depth,
+        find_only_one_tinue,
+        solver,
+        limits,
+    )?;
+
+    let mut rows = vec![];
+    let mut missed_rows = vec![];
+    for ply_result in ply_results.iter() {
+        if let Some(tinue) = &ply_result.tinue {
+            if ply_result.missed_tinue {
+                missed_rows.push(MissedTinueRow {
+                    gameid: game.id,
+                    source: game.source.clone(),
+                    size: game.size,
+                    ply: ply_result.ply,
+                    side_to_move: color_name(ply_result.side_to_move),
+                    tinue_depth: tinue.depth,
+                    tinue: tinue_to_json(Some(tinue), true)?,
+                    played_move: ply_result.played_move.clone(),
+                });
+            }
+            rows.push(PlyTinueRow {
+                gameid: game.id,
+                source: game.source.clone(),
+                size: game.size,
+                ply: ply_result.ply,
+                side_to_move: color_name(ply_result.side_to_move),
+                tinue_depth: tinue.depth,
+                tinue: tinue_to_json(Some(tinue), find_only_one_tinue)?,
+            });
+        }
+    }
+
+    let tinue_plies = rows
+        .iter()
+        .map(|row| {
+            let ply_result = &ply_results[row.ply as usize];
+            TinuePlyRecord {
+                ply: row.ply,
+                side: row.side_to_move,
+                depth: row.tinue_depth,
+                played: ply_result.played_move.clone(),
+                missed: ply_result.missed_tinue,
+            }
+        })
+        .collect();
+    let record = ScanRecord::Plies(PliesRecord {
+        id: game.id,
+        size: game.size,
+        result: game.result.clone(),
+        max_depth,
+        plies: ply_results.len() as u32,
+        first_tinue_ply: rows.first().map(|row| row.ply),
+        time_ms: timer.elapsed().as_millis() as u64,
+        stats: stats.clone(),
+        tinue_plies,
+    });
+
+    Ok(GameScan {
+        results: GameResults::Plies(rows, missed_rows),
+        stats,
+        record,
+    })
+}
+
+/// Searches `game` for a position in which the side to move has only a few replies that escape a Tinue
+fn handle_defense_game(
+    game: &Game,
+    max_depth: u32,
+    plies_to_undo: u32,
+    max_saving_moves: usize,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<GameScan, TinueFinderError> {
+    let timer = Instant::now();
+
+    let (saving_moves, stats) = find_defense_sized(
+        game.size,
+        &game.moves,
+        plies_to_undo,
+        max_depth,
+        max_saving_moves,
+        solver,
+        limits,
+    )?;
+
+    let row = match &saving_moves {
+        Some(saving_moves) => Some(DefensePuzzleRow {
+            gameid: game.id,
+            source: game.source.clone(),
+            size: game.size,
+            plies_to_undo,
+            tinue_depth: saving_moves.tinue_depth,
+            saving_moves: serde_json::to_string(&saving_moves.moves)?,
+        }),
+        None => None,
+    };
+    let record = ScanRecord::Defense(DefenseRecord {
+        id: game.id,
+        size: game.size,
+        result: game.result.clone(),
+        max_depth,
+        moves_to_undo: plies_to_undo,
+        time_ms: timer.elapsed().as_millis() as u64,
+        stats: stats.clone(),
+        depth: saving_moves
+            .as_ref()
+            .map(|saving_moves| saving_moves.tinue_depth),
+        saving_moves: saving_moves.map(|saving_moves| saving_moves.moves),
+    });
+
+    Ok(GameScan {
+        results: GameResults::Defense(row),
+        stats,
+        record,
+    })
+}
+
+/// Searches the games of `--db` or `--ptn` and stores the results in `--db`
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let board_size = get_arg_number(matches, "board_size")?;
+    let plies_to_undo = get_arg_number(matches, "plies_to_undo")?;
+    let max_depth = get_max_depth(matches)?;
+    let number_of_threads = get_arg_number(matches, "threads")?;
+    let min_game_id = get_arg_number(matches, "start_id")?;
+    let db_path = matches.value_of("database");
+    let ptn_path = matches.value_of("ptn");
+    let test = matches.occurrences_of("test") > 0;
+    let resume = matches.occurrences_of("resume") > 0;
+    let multi_tinue = matches.occurrences_of("multi_tinue") > 0;
+    let compact_tree = matches.occurrences_of("compact_tree") > 0;
+    let every_ply = matches.occurrences_of("every_ply") > 0;
+    let defense = matches.occurrences_of("defense") > 0;
+    let max_saving_moves = get_arg_number(matches, "max_saving_moves")? as usize;
+    let solver = get_solver(matches)?;
+    let max_time_secs = get_optional_arg_number(matches, "max_time")?;
+    let limits = get_search_limits(matches)?;
+
+    if !every_ply && plies_to_undo <= 1 {
+        return Err(TinueFinderError::InvalidArgument(
+            "plies_to_undo must be greater than 1 to make sense".to_string(),
+        ));
+    }
+    if number_of_threads == 0 {
+        return Err(TinueFinderError::InvalidArgument(
+            "at least 1 thread is required to run".to_string(),
+        ));
+    }
+    let number_of_threads = number_of_threads as usize;
+
+    let output = Output::from_matches(matches)?;
+    let batch_settings = BatchSettings::from_matches(matches)?;
+    output.info(&format!(
+        "test={} resume={} multi_tinue={} compact_tree={} every_ply={} defense={} board_size={} plies_to_undo={} max_depth={} min_game_id={} db_path={} ptn_path={} threads={} solver={:?} max_nodes={:?} max_time={:?}",
+        test, resume, multi_tinue, compact_tree, every_ply, defense, board_size, plies_to_undo, max_depth, min_game_id,
+        db_path.unwrap_or_default(), ptn_path.unwrap_or_default(), number_of_threads, solver, limits.max_nodes, max_time_secs
+    ));
+
+    // Configure maximum number of threads used
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(number_of_threads)
+        .build_global()
+        .map_err(|err| TinueFinderError::Io(io::Error::new(io::ErrorKind::Other, err)))?;
+
+    let conn = match db_path {
+        Some(db_path) => db::open(db_path)?,
+        None if test => Connection::open_in_memory()?,
+        None => {
+            return Err(TinueFinderError::InvalidArgument(
+                "--db is required to store the tinues, use --test to only log them".to_string(),
+            ))
+        }
+    };
+
+    let source = open_game_source(matches)?;
+    let scan_run = ScanRunRow {
+        source: source.name(),
+        size: board_size,
+        mode: if every_ply {
+            "every_ply"
+        } else if defense {
+            "defense"
+        } else {
+            "tinues"
+        },
+        // Every position is searched, so the number of plies to undo does not matter
+        plies_to_undo: if every_ply { 0 } else { plies_to_undo },
+        max_depth,
+        multi_tinue,
+        solver: format!("{:?}", solver),
+        max_nodes: limits.max_nodes,
+        max_time_secs,
+    };
+
+    // Tinues are only stored with a scan run, so there is none in test mode
+    let run_id = if test {
+        None
+    } else {
+        if every_ply {
+            db::create_ply_tinues_table(&conn)?;
+            db::create_missed_tinues_table(&conn)?;
+        } else if defense {
+            db::create_defense_puzzles_table(&conn)?;
+        } else {
+            db::create_tinues_table(&conn)?;
+        }
+        db::create_scan_tables(&conn)?;
+        Some(db::insert_scan_run(&conn, &scan_run)?)
+    };
+    // Lower bounds for the search, only the plain tinue search can make use of them
+    let no_tinue_depths = if run_id.is_some() && scan_run.mode == "tinues" {
+        db::select_no_tinue_depths(&conn, &scan_run.source, board_size, plies_to_undo)?
+    } else {
+        HashMap::new()
+    };
+    let scanned_game_ids = if resume {
+        db::select_scanned_game_ids(&conn, &scan_run)?
+    } else {
+        HashSet::new()
+    };
+
+    if resume {
+        output.info(&format!(
+            "{} games were already scanned with these settings",
+            scanned_game_ids.len()
+        ));
+    }
+
+    // From now on only the writer thread uses the connection
+    let writer = run_id.map(|run_id| {
+        let error_output = output.clone();
+        DbWriter::spawn(
+            conn,
+            batch_settings,
+            move |conn, (game_id, result): &ScannedGame| {
+                store_results(conn, run_id, *game_id, result)
+            },
+            move |(game_id, _), err| {
+                error_output.warn(&format!(
+                    "Failed to store results of game #{}: {}",
+                    game_id, err
+                ))
+            },
+        )
+    });
+
+    install_shutdown_handler(Arc::clone(&limits.cancel), output.clone())?;
+
+    let filter = GameFilter {
+        board_size,
+        min_game_id,
+    };
+    // Bounded, so that the reader stays only a few games ahead of the workers
+    let (game_sender, game_receiver) = mpsc::sync_channel(2 * number_of_threads);
+    let reader = spawn_game_reader(
+        source,
+        filter,
+        scanned_game_ids,
+        Arc::clone(&limits.cancel),
+        output.clone(),
+        game_sender,
+    );
+    let game_receiver = Mutex::new(game_receiver);
+    // Games that were skipped or whose search was cancelled because of a shutdown
+    let unscanned = Mutex::new(vec![]);
+
+    let scan_game = |game: Game, sender: Option<&Sender<ScannedGame>>| {
+        if limits.cancel.load(Ordering::SeqCst) {
+            unscanned.lock().unwrap().push(game.id);
+            return;
+        }
+        output.info(&format!(
+            "Thread #{} Processing game #{}",
+            current_thread_index().unwrap(),
+            game.id
+        ));
+        let result = if every_ply {
+            handle_game_plies(&game, max_depth, !multi_tinue, solver, &limits)
+        } else if defense {
+            handle_defense_game(
+                &game,
+                max_depth,
+                plies_to_undo,
+                max_saving_moves,
+                solver,
+                &limits,
+            )
+        } else {
+            // Known to have no Tinue up to this depth from earlier scans
+            let min_depth = no_tinue_depths.get(&game.id).map_or(1, |d| d + 2);
+            handle_game(
+                &game,
+                min_depth,
+                max_depth,
+                plies_to_undo,
+                !multi_tinue,
+                compact_tree,
+                solver,
+                &limits,
+            )
+        };
+
+        if let Err(TinueFinderError::SearchAborted(SearchAborted::Cancelled)) = result {
+            // Not recorded, so that `--resume` scans the game again
+            unscanned.lock().unwrap().push(game.id);
+            return;
+        }
+        match &result {
+            Ok(scan) => {
+                if let Err(err) = output.record(&scan.record) {
+                    output.warn(&format!(
+                        "Failed to write the results of game #{}: {}",
+                        game.id, err
+                    ));
+                }
+            }
+            Err(err) => output.warn(&format!("Skipping game #{}: {}", game.id, err)),
+        }
+        if let Some(sender) = sender {
+            if sender.send((game.id, result)).is_err() {
+                output.warn(&format!(
+                    "Failed to store results of game #{}: the database writer stopped",
+                    game.id
+                ));
+            }
+        }
+    };
+
+    let scan_game = &scan_game;
+    let game_receiver = &game_receiver;
+    rayon::scope_fifo(|scope| {
+        for _ in 0..number_of_threads {
+            let sender = writer.as_ref().map(DbWriter::sender);
+            scope.spawn_fifo(move |_| loop {
+                // The lock is released before the game is searched
+                let received = game_receiver.lock().unwrap().recv();
+                match received {
+                    Ok(game) => scan_game(game, sender.as_ref()),
+                    Err(_) => break,
+                }
+            });
+        }
+    });
+
+    // If the source failed, the run is finished like an interrupted one before the error is returned
+    let (first_unread, read_error) = match reader.join().expect("the game reader thread panicked") {
+        Ok(first_unread) => (first_unread, None),
+        Err(TinueFinderError::SourceInterrupted { next_id, reason }) => (
+            Some(next_id),
+            Some(TinueFinderError::SourceInterrupted { next_id, reason }),
+        ),
+        Err(err) => return Err(err),
+    };
+    let resume_from = unscanned
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .chain(first_unread)
+        .min();
+    if let (Some(writer), Some(run_id)) = (writer, run_id) {
+        let conn = writer.finish()?;
+        db::finish_scan_run(&conn, run_id, resume_from)?;
+    }
+    output.flush()?;
+
+    if let Some(first_game_id) = resume_from {
+        output.warn(&format!(
+            "Interrupted, the first game that was not scanned is #{}",
+            first_game_id
+        ));
+        output.info(&format!(
+            "Continue with: {}",
+            resume_command(first_game_id, test)
+        ));
+    }
+
+    match read_error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Reads the games on a thread of its own and sends them to the workers, skipping those in `scanned_game_ids`.
+///
+/// Stops early when `cancel` is set and returns the id of the first game that was not sent.
+/// Unreadable games are skipped, but if the source fails its `TinueFinderError::SourceInterrupted` is returned.
+fn spawn_game_reader(
+    source: Box<dyn GameSource>,
+    filter: GameFilter,
+    scanned_game_ids: HashSet<u32>,
+    cancel: Arc<AtomicBool>,
+    output: Output,
+    sender: SyncSender<Game>,
+) -> JoinHandle<Result<Option<u32>, TinueFinderError>> {
+    thread::spawn(move || {
+        for game in source.road_wins(&filter)? {
+            let game = match game {
+                Ok(game) => game,
+                Err(err @ TinueFinderError::SourceInterrupted { .. }) => return Err(err),
+                Err(err) => {
+                    output.warn(&format!("Skipping unreadable game: {}", err));
+                    continue;
+                }
+            };
+            if scanned_game_ids.contains(&game.id) {
+                continue;
+            }
+            if cancel.load(Ordering::SeqCst) {
+                return Ok(Some(game.id));
+            }
+            if sender.send(game).is_err() {
+                // The workers stopped
+                break;
+            }
+        }
+        Ok(None)
+    })
+}
+
+/// Cancels the searches on Ctrl-C or SIGTERM, a second signal exits immediately
+fn install_shutdown_handler(
+    cancel: Arc<AtomicBool>,
+    output: Output,
+) -> Result<(), TinueFinderError> {
+    ctrlc::set_handler(move || {
+        if cancel.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        output.warn("Stopping after the running searches, press Ctrl-C again to exit immediately");
+    })
+    .map_err(|err| TinueFinderError::Io(io::Error::new(io::ErrorKind::Other, err)))
+}
+
+/// The command line of this scan, changed to skip the games that were already scanned.
+///
+/// Without a database the scanned games are unknown, so it starts at `first_game_id` instead.
+fn resume_command(first_game_id: u32, test: bool) -> String {
+    let mut args = vec![];
+    let mut skip_value = false;
+    for arg in env::args() {
+        if skip_value {
+            skip_value = false;
+            continue;
+        }
+        if test && (arg == "-s" || arg == "--start-id") {
+            skip_value = true;
+            continue;
+        }
+        if test && is_start_id_with_value(&arg) {
+            continue;
+        }
+        args.push(match arg.contains(char::is_whitespace) {
+            true => format!("\"{}\"", arg),
+            false => arg,
+        });
+    }
+    if test {
+        args.push(format!("--start-id {}", first_game_id));
+    } else if !args.iter().any(|arg| arg == "--resume") {
+        args.push("--resume".to_string());
+    }
+    args.join(" ")
+}
+
+/// Returns `true` if `arg` is `--start-id` together with its value, i.e. `--start-id=<id>` or `-s<id>`
+fn is_start_id_with_value(arg: &str) -> bool {
+    arg.starts_with("--start-id=")
+        || arg.strip_prefix("-s").map_or(false, |id| {
+            !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+/// Everything a worker found in a single game
+struct GameScan {
+    results: GameResults,
+    stats: SearchStats,
+    /// Written to `--output`
+    record: ScanRecord,
+}
+
+/// A line of the output of a scan, depending on its mode
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ScanRecord {
+    Tinue(TinueRecord),
+    Plies(PliesRecord),
+    Defense(DefenseRecord),
+}
+
+#[derive(Serialize)]
+struct TinueRecord {
+    id: u32,
+    size: u32,
+    result: String,
+    #[serde(rename = "min-depth")]
+    min_depth: u32,
+    #[serde(rename = "max-depth")]
+    max_depth: u32,
+    /// `0` if there is no Tinue
+    depth: u32,
+    #[serde(rename = "movesToUndo")]
+    moves_to_undo: u32,
+    #[serde(rename = "timeMs")]
+    time_ms: u64,
+    stats: SearchStats,
+    tinue: Value,
+}
+
+#[derive(Serialize)]
+struct PliesRecord {
+    id: u32,
+    size: u32,
+    result: String,
+    #[serde(rename = "max-depth")]
+    max_depth: u32,
+    plies: u32,
+    /// The ply in which the game was lost, unless the Tinue was missed
+    #[serde(rename = "firstTinuePly")]
+    first_tinue_ply: Option<u32>,
+    #[serde(rename = "timeMs")]
+    time_ms: u64,
+    stats: SearchStats,
+    #[serde(rename = "tinuePlies")]
+    tinue_plies: Vec<TinuePlyRecord>,
+}
+
+#[derive(Serialize)]
+struct TinuePlyRecord {
+    ply: u32,
+    side: &'static str,
+    depth: u32,
+    played: Mov,
+    missed: bool,
+}
+
+#[derive(Serialize)]
+struct DefenseRecord {
+    id: u32,
+    size: u32,
+    result: String,
+    #[serde(rename = "max-depth")]
+    max_depth: u32,
+    #[serde(rename = "movesToUndo")]
+    moves_to_undo: u32,
+    #[serde(rename = "timeMs")]
+    time_ms: u64,
+    stats: SearchStats,
+    /// Length of the threatened Tinue, not set if there is no defense puzzle
+    depth: Option<u32>,
+    #[serde(rename = "savingMoves")]
+    saving_moves: Option<Vec<Mov>>,
+}
+
+/// The rows found in a single game, depending on the mode of the scan
+enum GameResults {
+    /// `depth` is `0` if there is no Tinue, `row` is only set for Tinues longer than a single ply
+    Tinue {
+        depth: u32,
+        row: Option<TinueGameRow>,
+    },
+    Plies(Vec<PlyTinueRow>, Vec<MissedTinueRow>),
+    Defense(Option<DefensePuzzleRow>),
+}
+
+impl GameResults {
+    /// Returns the outcome and the length of the Tinue to record in `scanned_games`
+    fn outcome(&self) -> (ScanOutcome, Option<u32>) {
+        match self {
+            GameResults::Tinue { depth: 0, .. } => (ScanOutcome::NoTinue, None),
+            GameResults::Tinue { depth: 1, .. } => (ScanOutcome::ImmediateWin, Some(1)),
+            GameResults::Tinue { depth, .. } => (ScanOutcome::Tinue, Some(*depth)),
+            GameResults::Plies(rows, _) => match rows.iter().map(|r| r.tinue_depth).max() {
+                None => (ScanOutcome::NoTinue, None),
+                Some(1) => (ScanOutcome::ImmediateWin, Some(1)),
+                Some(depth) => (ScanOutcome::Tinue, Some(depth)),
+            },
+            GameResults::Defense(None) => (ScanOutcome::NoTinue, None),
+            GameResults::Defense(Some(r)) => (ScanOutcome::Tinue, Some(r.tinue_depth)),
+        }
+    }
+}
+
+/// A game id and what was found in it, sent to the database writer
+type ScannedGame = (u32, Result<GameScan, TinueFinderError>);
+
+/// Stores the results of a game and marks it as scanned.
+///
+/// The writer runs this within a savepoint, so that an interrupted scan never records a game
+/// without its results. Games that could not be analyzed are recorded with their error,
+/// games whose search exceeded the limits as aborted.
+fn store_results(
+    conn: &Connection,
+    run_id: i64,
+    game_id: u32,
+    result: &Result<GameScan, TinueFinderError>,
+) -> Result<(), TinueFinderError> {
+    match result {
+        Ok(GameScan {
+            results: GameResults::Tinue { row, .. },
+            ..
+        }) => {
+            if let Some(r) = row {
+                db::insert_tinue(conn, r)?;
+            }
+        }
+        Ok(GameScan {
+            results: GameResults::Plies(rows, missed_rows),
+            ..
+        }) => {
+            for r in rows.iter() {
+                db::insert_ply_tinue(conn, r)?;
+            }
+            for r in missed_rows.iter() {
+                db::insert_missed_tinue(conn, r)?;
+            }
+        }
+        Ok(GameScan {
+            results: GameResults::Defense(row),
+            ..
+        }) => {
+            if let Some(r) = row {
+                db::insert_defense_puzzle(conn, r)?;
+            }
+        }
+        Err(_) => {}
+    }
+
+    let (outcome, tinue_depth) = match result {
+        Ok(scan) => scan.results.outcome(),
+        Err(TinueFinderError::SearchAborted(_)) => (ScanOutcome::Aborted, None),
+        Err(_) => (ScanOutcome::Error, None),
+    };
+    db::insert_scanned_game(
+        conn,
+        &ScannedGameRow {
+            run_id,
+            gameid: game_id,
+            outcome,
+            tinue_depth,
+            error: result.as_ref().err().map(|err| err.to_string()),
+            stats: result
+                .as_ref()
+                .ok()
+                .map(|scan| serde_json::to_string(&scan.stats))
+                .transpose()?,
+        },
+    )?;
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::fs;
+use tinue_finder::serialize::tinue_json_schema;
+use tinue_finder::TinueFinderError;
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("schema")
+        .about("Prints the JSON schema of the tinues stored in the `tinue` columns")
+        .arg(
+            Arg::with_name("out")
+                .short("o")
+                .long("out")
+                .takes_value(true)
+                .help("Writes the schema to this file instead of stdout")
+                .required(false),
+        )
+}
+
+/// Writes the schema generated from `VersionedTinue` to `--out` or stdout
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let schema = serde_json::to_string_pretty(&tinue_json_schema()?)?;
+    match matches.value_of("out") {
+        Some(path) => fs::write(path, schema + "\n")?,
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
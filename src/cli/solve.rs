@@ -0,0 +1,123 @@
+use board_game_traits::Color;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde::Serialize;
+use serde_json::Value;
+use tinue_finder::dot::tinue_to_dot;
+use tinue_finder::ptn::ptn_from_tps;
+use tinue_finder::serialize::{color_name, get_longest_sequence, move_list_to_vec, tinue_to_value};
+use tinue_finder::tps::{solve_tps_sized, tps_board_size, with_side_to_move};
+use tinue_finder::TinueFinderError;
+
+use super::output::{log_format_arg, output_arg, Output};
+use super::{get_max_depth, get_solver, solver_arg};
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("solve")
+        .about("Searches a single position given as TPS for a Tinue")
+        .arg(
+            Arg::with_name("tps")
+                .long("tps")
+                .takes_value(true)
+                .help("Position in Tak Positional System, e.g. \"x5/x5/x5/x5/x5 1 1\"")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("side")
+                .long("side")
+                .takes_value(true)
+                .possible_values(&["white", "black"])
+                .help("Side to move, overrides the player to move of the TPS")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("max_depth")
+                .short("d")
+                .long("max-depth")
+                .takes_value(true)
+                .help("Maximum depth/length of a Tinue in plies (must be odd)")
+                .required(false)
+                .default_value("5"),
+        )
+        .arg(
+            Arg::with_name("multi_tinue")
+                .short("m")
+                .long("multi-tinue")
+                .help("Searches for all available tinues and how to handle all possible opponent replies")
+                .required(false),
+        )
+        .arg(solver_arg())
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["json", "ptn", "dot"])
+                .help("Output format. `ptn` only contains the longest line of the Tinue, `dot` is a Graphviz graph of the tree of moves")
+                .required(false)
+                .default_value("json"),
+        )
+        .arg(output_arg())
+        .arg(log_format_arg())
+}
+
+/// The Tinue of a position, written as a JSON line
+#[derive(Serialize)]
+struct SolveRecord<'a> {
+    tps: &'a str,
+    side: &'static str,
+    #[serde(rename = "max-depth")]
+    max_depth: u32,
+    /// `0` if there is no Tinue
+    depth: u32,
+    /// A `VersionedTinue`, or `null` if there is no Tinue
+    tinue: Value,
+}
+
+/// Searches the position given with `--tps` and prints the Tinue
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let tps = matches.value_of("tps").unwrap();
+    let side_to_move = match matches.value_of("side") {
+        Some("white") => Some(Color::White),
+        Some("black") => Some(Color::Black),
+        _ => None,
+    };
+    let max_depth = get_max_depth(matches)?;
+    let multi_tinue = matches.occurrences_of("multi_tinue") > 0;
+    let solver = get_solver(matches)?;
+    let output = Output::from_matches(matches)?;
+
+    let tps = match side_to_move {
+        Some(color) => with_side_to_move(tps, color)?,
+        None => tps.to_string(),
+    };
+    let tps = tps.as_str();
+
+    let result = solve_tps_sized(tps, None, max_depth, !multi_tinue, solver)?;
+
+    match matches.value_of("format") {
+        Some("ptn") => {
+            let line = result
+                .as_ref()
+                .and_then(|r| r.result.first())
+                .map(|m| move_list_to_vec(get_longest_sequence(m).1))
+                .unwrap_or_default();
+            output.text(&ptn_from_tps(tps, tps_board_size(tps)?, &line))?;
+        }
+        Some("dot") => {
+            let moves = result.as_ref().map_or(&[][..], |r| &r.result[..]);
+            output.text(&tinue_to_dot(moves, tps))?;
+        }
+        _ => {
+            output.record(&SolveRecord {
+                tps,
+                side: match tps.split_whitespace().nth(1) {
+                    Some("2") => color_name(Color::Black),
+                    _ => color_name(Color::White),
+                },
+                max_depth,
+                depth: result.as_ref().map_or(0, |r| r.depth),
+                tinue: tinue_to_value(result.as_ref(), !multi_tinue)?,
+            })?;
+        }
+    }
+    output.flush()
+}
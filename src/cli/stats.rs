@@ -0,0 +1,52 @@
+use clap::{App, ArgMatches, SubCommand};
+use serde::Serialize;
+use tinue_finder::db;
+use tinue_finder::TinueFinderError;
+
+use super::database_arg;
+use super::output::{log_format_arg, output_arg, Output};
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("stats")
+        .about("Prints the number of stored tinues per board size and depth")
+        .arg(
+            database_arg()
+                .help("Path of the database with the `tinues` table")
+                .required(true),
+        )
+        .arg(output_arg())
+        .arg(log_format_arg())
+}
+
+/// Number of stored tinues of a board size and depth, written as a JSON line
+#[derive(Serialize)]
+struct StatsRecord {
+    size: u32,
+    depth: u32,
+    tinues: u32,
+    games: u32,
+    duplicates: u32,
+}
+
+/// Writes the number of tinues in `--db` of each board size and depth as a JSON line
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let db_path = matches.value_of("database").unwrap();
+    let output = Output::from_matches(matches)?;
+    let conn = db::open_read_only(db_path)?;
+
+    let rows = db::select_tinue_stats(&conn)?;
+    for row in rows.iter() {
+        output.record(&StatsRecord {
+            size: row.size,
+            depth: row.tinue_depth,
+            tinues: row.tinues,
+            games: row.games,
+            duplicates: row.duplicates,
+        })?;
+    }
+    output.flush()?;
+    let total: u32 = rows.iter().map(|row| row.tinues).sum();
+    output.info(&format!("{} tinues in total", total));
+
+    Ok(())
+}
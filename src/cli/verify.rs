@@ -0,0 +1,126 @@
+use clap::{App, ArgMatches, SubCommand};
+use serde::Serialize;
+use tinue_finder::db::{self, StoredTinueRow};
+use tinue_finder::game::{do_it_sized, Game};
+use tinue_finder::{SearchLimits, Solver, TinueFinderError};
+
+use super::output::{log_format_arg, output_arg, Output};
+use super::{
+    board_size_arg, database_arg, get_optional_arg_number, get_search_limits, load_games,
+    max_nodes_arg, max_time_arg, ptn_arg,
+};
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("verify")
+        .about("Searches the positions of the stored tinues again and reports those whose depth is wrong")
+        .arg(
+            database_arg()
+                .help("Path of the database with the `tinues` table. Games are read from its `games` table unless `--ptn` is given")
+                .required(true),
+        )
+        .arg(ptn_arg())
+        .arg(board_size_arg().required(false))
+        .arg(max_nodes_arg().help("Skips a tinue after searching this many positions"))
+        .arg(max_time_arg().help("Skips a tinue after searching for this many seconds"))
+        .arg(output_arg())
+        .arg(log_format_arg())
+}
+
+/// Result of checking a stored tinue, written as a JSON line
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyRecord {
+    id: u32,
+    gameid: u32,
+    source: String,
+    size: u32,
+    moves_to_undo: u32,
+    stored_depth: u32,
+    /// `0` if there is no Tinue within the stored depth
+    depth: u32,
+    valid: bool,
+}
+
+/// Searches the position of `row` for a Tinue of at most the stored depth.
+///
+/// Uses IDDFS, which finds the shortest Tinue, so a shorter Tinue than the stored one is reported as well.
+/// Fails with `TinueFinderError::SearchAborted` if the search exceeds `limits`.
+fn verify_tinue(
+    row: &StoredTinueRow,
+    game: &Game,
+    limits: &SearchLimits,
+) -> Result<u32, TinueFinderError> {
+    let (result, _stats) = do_it_sized(
+        game.size,
+        &game.moves,
+        row.tinue.plies_to_undo,
+        1,
+        row.tinue.tinue_depth,
+        true,
+        Solver::Iddfs,
+        limits,
+    )?;
+    Ok(result.map_or(0, |r| r.depth))
+}
+
+/// Checks every stored tinue of `--db` and writes the result of each as a JSON line
+pub fn run(matches: &ArgMatches) -> Result<(), TinueFinderError> {
+    let db_path = matches.value_of("database").unwrap();
+    let board_size = get_optional_arg_number(matches, "board_size")?;
+    let limits = get_search_limits(matches)?;
+    let output = Output::from_matches(matches)?;
+
+    let conn = db::open_read_only(db_path)?;
+    let rows = db::select_tinues(&conn, board_size)?;
+
+    let games = load_games(matches, &rows)?;
+
+    let mut verified = 0;
+    let mut invalid = 0;
+    for row in rows.iter() {
+        let game = games
+            .get(&row.tinue.gameid)
+            .filter(|game| game.source == row.tinue.game_source());
+        let game = match game {
+            Some(game) => game,
+            None => {
+                output.warn(&format!(
+                    "Skipping tinue #{}: game #{} of {} not found",
+                    row.id,
+                    row.tinue.gameid,
+                    row.tinue.game_source()
+                ));
+                continue;
+            }
+        };
+        let depth = match verify_tinue(row, game, &limits) {
+            Ok(depth) => depth,
+            Err(err) => {
+                output.warn(&format!("Skipping tinue #{}: {}", row.id, err));
+                continue;
+            }
+        };
+        verified += 1;
+        let valid = depth == row.tinue.tinue_depth;
+        if !valid {
+            invalid += 1;
+        }
+        output.record(&VerifyRecord {
+            id: row.id,
+            gameid: row.tinue.gameid,
+            source: row.tinue.game_source().to_string(),
+            size: row.tinue.size,
+            moves_to_undo: row.tinue.plies_to_undo,
+            stored_depth: row.tinue.tinue_depth,
+            depth,
+            valid,
+        })?;
+    }
+    output.flush()?;
+    output.info(&format!(
+        "Verified {} tinues, {} invalid",
+        verified, invalid
+    ));
+
+    Ok(())
+}
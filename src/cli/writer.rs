@@ -0,0 +1,131 @@
+use clap::{Arg, ArgMatches};
+use rusqlite::Connection;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tinue_finder::TinueFinderError;
+
+use super::get_arg_number;
+
+/// When the writer commits the rows it received
+#[derive(Debug, Clone, Copy)]
+pub struct BatchSettings {
+    /// Commits after this many items
+    pub max_items: usize,
+    /// Commits when the oldest uncommitted item is this old
+    pub max_delay: Duration,
+}
+
+pub fn batch_size_arg() -> Arg<'static, 'static> {
+    Arg::with_name("batch_size")
+        .long("batch-size")
+        .takes_value(true)
+        .help("Number of games whose results are written to the database in a single transaction")
+        .required(false)
+        .default_value("100")
+}
+
+pub fn batch_secs_arg() -> Arg<'static, 'static> {
+    Arg::with_name("batch_secs")
+        .long("batch-secs")
+        .takes_value(true)
+        .help("Maximum number of seconds before the results of a transaction are committed")
+        .required(false)
+        .default_value("5")
+}
+
+impl BatchSettings {
+    /// Reads `--batch-size` and `--batch-secs`
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, TinueFinderError> {
+        let max_items = get_arg_number(matches, "batch_size")?;
+        if max_items == 0 {
+            return Err(TinueFinderError::InvalidArgument(
+                "batch-size must be at least 1".to_string(),
+            ));
+        }
+        Ok(BatchSettings {
+            max_items: max_items as usize,
+            max_delay: Duration::from_secs(get_arg_number(matches, "batch_secs")?.into()),
+        })
+    }
+}
+
+/// Owns the database connection on a thread of its own, so that the workers never wait for SQLite.
+///
+/// Items are sent through a channel and written in batched transactions.
+/// Each item is written within a savepoint: an item that fails is rolled back on its own
+/// and reported, the others of the batch are still committed.
+pub struct DbWriter<T> {
+    sender: Sender<T>,
+    handle: JoinHandle<Result<Connection, TinueFinderError>>,
+}
+
+impl<T: Send + 'static> DbWriter<T> {
+    /// Starts the writer thread. `write` stores a single item, `on_error` is called if that fails.
+    pub fn spawn<W, E>(conn: Connection, settings: BatchSettings, write: W, on_error: E) -> Self
+    where
+        W: Fn(&Connection, &T) -> Result<(), TinueFinderError> + Send + 'static,
+        E: Fn(&T, TinueFinderError) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<T>();
+        let handle = thread::spawn(move || {
+            let mut pending = 0;
+            let mut batch_started = Instant::now();
+            loop {
+                let received = if pending == 0 {
+                    receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    let remaining = settings.max_delay.saturating_sub(batch_started.elapsed());
+                    receiver.recv_timeout(remaining)
+                };
+                match received {
+                    Ok(item) => {
+                        if pending == 0 {
+                            conn.execute_batch("BEGIN")?;
+                            batch_started = Instant::now();
+                        }
+                        conn.execute_batch("SAVEPOINT item")?;
+                        match write(&conn, &item) {
+                            Ok(()) => conn.execute_batch("RELEASE item")?,
+                            Err(err) => {
+                                conn.execute_batch("ROLLBACK TO item; RELEASE item")?;
+                                on_error(&item, err);
+                            }
+                        }
+                        pending += 1;
+                        if pending >= settings.max_items {
+                            conn.execute_batch("COMMIT")?;
+                            pending = 0;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        conn.execute_batch("COMMIT")?;
+                        pending = 0;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        if pending > 0 {
+                            conn.execute_batch("COMMIT")?;
+                        }
+                        return Ok(conn);
+                    }
+                }
+            }
+        });
+        DbWriter { sender, handle }
+    }
+
+    /// A sender for a worker thread
+    pub fn sender(&self) -> Sender<T> {
+        self.sender.clone()
+    }
+
+    /// Waits until every item that was sent is committed and returns the connection.
+    ///
+    /// All senders returned by `sender` must have been dropped, otherwise this waits forever.
+    pub fn finish(self) -> Result<Connection, TinueFinderError> {
+        drop(self.sender);
+        self.handle
+            .join()
+            .expect("the database writer thread panicked")
+    }
+}
@@ -0,0 +1,203 @@
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use crate::{Mov, TinueFinderError, TinueMove};
+
+/// Every encoded tree starts with these bytes, followed by `TREE_FORMAT_VERSION`
+const MAGIC: &[u8; 3] = b"TFT";
+const TREE_FORMAT_VERSION: u8 = 1;
+
+/// The moves that can be played in a position, each with the node of the replies to it.
+///
+/// Moves and nodes are referenced by their index in the move table and the node list.
+type Node = Vec<(u32, Option<u32>)>;
+
+/// Builds the move table and the list of distinct nodes of a tree.
+///
+/// Identical subtrees are stored once, so the tree becomes a DAG.
+/// The replies to a move always come before it in `nodes`.
+#[derive(Default)]
+struct TreeEncoder {
+    moves: Vec<Mov>,
+    move_ids: HashMap<Mov, u32>,
+    nodes: Vec<Node>,
+    node_ids: HashMap<Node, u32>,
+}
+
+impl TreeEncoder {
+    fn move_id(&mut self, mv: &Mov) -> u32 {
+        if let Some(&id) = self.move_ids.get(mv) {
+            return id;
+        }
+        let id = self.moves.len() as u32;
+        self.moves.push(mv.clone());
+        self.move_ids.insert(mv.clone(), id);
+        id
+    }
+
+    /// Returns the index of the node of `tinue_moves`
+    fn add_node(&mut self, tinue_moves: &[TinueMove]) -> u32 {
+        let mut node = Node::with_capacity(tinue_moves.len());
+        for tinue_move in tinue_moves {
+            let next = tinue_move.next.as_ref().map(|next| self.add_node(next));
+            node.push((self.move_id(&tinue_move.mv), next));
+        }
+        if let Some(&id) = self.node_ids.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len() as u32;
+        self.node_ids.insert(node.clone(), id);
+        self.nodes.push(node);
+        id
+    }
+}
+
+/// Encodes a tree of `TinueMove`s, e.g. the result of a search with `--multi-tinue`, in a few bytes.
+///
+/// Moves are replaced by their index in a table of the distinct moves of the tree,
+/// identical subtrees are stored once, and everything after the header is compressed with deflate.
+/// All numbers are written as LEB128 varints.
+pub fn encode_tinue_tree(tinue_moves: &[TinueMove]) -> Result<Vec<u8>, TinueFinderError> {
+    let mut encoder = TreeEncoder::default();
+    let root = encoder.add_node(tinue_moves);
+
+    let mut raw = vec![];
+    write_varint(&mut raw, encoder.moves.len() as u64);
+    for mv in encoder.moves.iter() {
+        write_varint(&mut raw, mv.len() as u64);
+        raw.extend_from_slice(mv.as_bytes());
+    }
+    write_varint(&mut raw, encoder.nodes.len() as u64);
+    for node in encoder.nodes.iter() {
+        write_varint(&mut raw, node.len() as u64);
+        for (mv, next) in node.iter() {
+            write_varint(&mut raw, *mv as u64);
+            // `0` if the move ends the Tinue
+            write_varint(&mut raw, next.map_or(0, |next| next as u64 + 1));
+        }
+    }
+    write_varint(&mut raw, root as u64);
+
+    let mut header = MAGIC.to_vec();
+    header.push(TREE_FORMAT_VERSION);
+    let mut deflate = DeflateEncoder::new(header, Compression::best());
+    deflate.write_all(&raw)?;
+    Ok(deflate.finish()?)
+}
+
+/// Decodes a tree written by `encode_tinue_tree`.
+///
+/// Shared subtrees are expanded again, so the result can be much larger than the encoded tree.
+pub fn decode_tinue_tree(bytes: &[u8]) -> Result<Vec<TinueMove>, TinueFinderError> {
+    let header_len = MAGIC.len() + 1;
+    if bytes.len() < header_len || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid_tree("not an encoded tinue tree"));
+    }
+    if bytes[MAGIC.len()] != TREE_FORMAT_VERSION {
+        return Err(TinueFinderError::UnsupportedFormatVersion(
+            bytes[MAGIC.len()].into(),
+        ));
+    }
+    let mut raw = vec![];
+    DeflateDecoder::new(&bytes[header_len..])
+        .read_to_end(&mut raw)
+        .map_err(|err| invalid_tree(&err.to_string()))?;
+    let mut reader = VarintReader { bytes: &raw };
+
+    let move_count = reader.read_len()?;
+    let mut moves = Vec::with_capacity(move_count);
+    for _ in 0..move_count {
+        let len = reader.read_len()?;
+        let mv = reader.read_bytes(len)?;
+        moves.push(String::from_utf8(mv.to_vec()).map_err(|_| invalid_tree("move is not UTF-8"))?);
+    }
+
+    let node_count = reader.read_len()?;
+    let mut nodes: Vec<Node> = Vec::with_capacity(node_count);
+    for _ in 0..node_count {
+        let len = reader.read_len()?;
+        let mut node = Node::with_capacity(len);
+        for _ in 0..len {
+            let mv = reader.read_varint()?;
+            if mv >= moves.len() as u64 {
+                return Err(invalid_tree("move index out of range"));
+            }
+            let next = match reader.read_varint()? {
+                0 => None,
+                // Replies are written first, which also rules out cycles
+                next if next <= nodes.len() as u64 => Some(next as u32 - 1),
+                _ => return Err(invalid_tree("node index out of range")),
+            };
+            node.push((mv as u32, next));
+        }
+        nodes.push(node);
+    }
+
+    let root = reader.read_varint()?;
+    if root >= nodes.len() as u64 {
+        return Err(invalid_tree("root index out of range"));
+    }
+    Ok(expand_node(&moves, &nodes, root as u32))
+}
+
+fn expand_node(moves: &[Mov], nodes: &[Node], id: u32) -> Vec<TinueMove> {
+    nodes[id as usize]
+        .iter()
+        .map(|(mv, next)| TinueMove {
+            mv: moves[*mv as usize].clone(),
+            next: next.map(|next| expand_node(moves, nodes, next)),
+        })
+        .collect()
+}
+
+fn invalid_tree(reason: &str) -> TinueFinderError {
+    TinueFinderError::InvalidTinueTree(reason.to_string())
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads the varints of a decompressed tree from the front
+struct VarintReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> VarintReader<'a> {
+    fn read_varint(&mut self) -> Result<u64, TinueFinderError> {
+        let mut value = 0u64;
+        for (i, byte) in self.bytes.iter().enumerate().take(10) {
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[i + 1..];
+                return Ok(value);
+            }
+        }
+        Err(invalid_tree("truncated or too long number"))
+    }
+
+    /// Reads a length, which must not exceed the remaining bytes
+    fn read_len(&mut self) -> Result<usize, TinueFinderError> {
+        let len = self.read_varint()?;
+        if len > self.bytes.len() as u64 {
+            return Err(invalid_tree("length exceeds the data"));
+        }
+        Ok(len as usize)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], TinueFinderError> {
+        if len > self.bytes.len() {
+            return Err(invalid_tree("truncated data"));
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+}
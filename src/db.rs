@@ -0,0 +1,616 @@
+use rusqlite::{params, Connection, OpenFlags};
+use std::collections::{HashMap, HashSet};
+
+use crate::game_source::PLAYTAK_SOURCE;
+use crate::TinueFinderError;
+
+/// A row of the `scan_runs` table.
+///
+/// Games scanned by a run with the same settings are skipped by `--resume`.
+pub struct ScanRunRow {
+    /// `GameSource::name` of the scanned games
+    pub source: String,
+    pub size: u32,
+    /// `"tinues"`, `"every_ply"` or `"defense"`
+    pub mode: &'static str,
+    pub plies_to_undo: u32,
+    pub max_depth: u32,
+    pub multi_tinue: bool,
+    pub solver: String,
+    /// Node budget of the searches of each game
+    pub max_nodes: Option<u64>,
+    /// Time budget of the searches of each game in seconds
+    pub max_time_secs: Option<u32>,
+}
+
+/// What the search found in a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanOutcome {
+    /// There is no Tinue within the maximum depth
+    NoTinue,
+    /// The side to move wins with a single ply
+    ImmediateWin,
+    /// A Tinue (or a defense puzzle, depending on the mode) was found
+    Tinue,
+    /// The game could not be analyzed, e.g. because of an illegal move
+    Error,
+    /// The search exceeded its limits or was cancelled, the game can be scanned again
+    Aborted,
+}
+
+impl ScanOutcome {
+    /// Name of the outcome as stored in the database
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScanOutcome::NoTinue => "no_tinue",
+            ScanOutcome::ImmediateWin => "immediate_win",
+            ScanOutcome::Tinue => "tinue",
+            ScanOutcome::Error => "error",
+            ScanOutcome::Aborted => "aborted",
+        }
+    }
+}
+
+/// A row of the `scanned_games` table
+pub struct ScannedGameRow {
+    pub run_id: i64,
+    pub gameid: u32,
+    pub outcome: ScanOutcome,
+    /// Length of the Tinue, if one was found
+    pub tinue_depth: Option<u32>,
+    /// Why the game could not be analyzed, only set for `ScanOutcome::Error`
+    pub error: Option<String>,
+    /// `SearchStats` of the game as JSON, not set if the search failed
+    pub stats: Option<String>,
+}
+
+/// A row of the `tinues` table
+pub struct TinueGameRow {
+    pub gameid: u32,
+    /// `GameSource::name` of the game. Missing in rows stored before it was introduced,
+    /// which were all read from playtak, see `game_source()`.
+    pub source: Option<String>,
+    pub size: u32,
+    pub plies_to_undo: u32,
+    pub tinue_depth: u32,
+    pub tinue: String,
+    /// Same for positions that only differ by a symmetry of the board or by swapping colours,
+    /// see `symmetry::canonical_key`. Missing in rows stored before it was introduced.
+    pub canonical_key: Option<String>,
+    /// The puzzle position, so that it can be shown without the `games` table.
+    /// This and the following columns are missing in rows stored before they were introduced.
+    pub tps: Option<String>,
+    /// `"white"` or `"black"`
+    pub side_to_move: Option<String>,
+    /// Number of plies played before the puzzle position
+    pub ply: Option<u32>,
+    /// The move that was played from the puzzle position in the game
+    pub played_move: Option<String>,
+    /// The complete tree of the Tinue, see `compact::encode_tinue_tree`. Only stored with `--compact-tree`.
+    pub tinue_tree: Option<Vec<u8>>,
+}
+
+impl TinueGameRow {
+    /// `GameSource::name` of the game, also for rows stored before it was introduced
+    pub fn game_source(&self) -> &str {
+        self.source.as_deref().unwrap_or(PLAYTAK_SOURCE)
+    }
+}
+
+/// A row of the `tinues` table as read back from the database
+pub struct StoredTinueRow {
+    pub id: u32,
+    pub tinue: TinueGameRow,
+    /// Id of the first tinue with the same canonical position, `None` if this is the first
+    pub duplicate_of: Option<u32>,
+}
+
+/// Number of stored tinues of a board size and depth
+pub struct TinueStatsRow {
+    pub size: u32,
+    pub tinue_depth: u32,
+    pub tinues: u32,
+    /// Number of different games the tinues were found in
+    pub games: u32,
+    /// Number of tinues whose position was already found in another game
+    pub duplicates: u32,
+}
+
+/// A position of a game in which the side to move had a Tinue, found by searching every ply
+pub struct PlyTinueRow {
+    pub gameid: u32,
+    /// `GameSource::name` of the game
+    pub source: String,
+    pub size: u32,
+    /// Number of plies played before the position
+    pub ply: u32,
+    /// `"white"` or `"black"`
+    pub side_to_move: &'static str,
+    pub tinue_depth: u32,
+    pub tinue: String,
+}
+
+/// A position in which the side to move had a Tinue but played a move that does not win
+pub struct MissedTinueRow {
+    pub gameid: u32,
+    /// `GameSource::name` of the game
+    pub source: String,
+    pub size: u32,
+    /// Number of plies played before the position
+    pub ply: u32,
+    /// `"white"` or `"black"`
+    pub side_to_move: &'static str,
+    pub tinue_depth: u32,
+    /// The longest winning line as a `VersionedTinue`
+    pub tinue: String,
+    pub played_move: String,
+}
+
+/// A position in which only a few replies escape a Tinue
+pub struct DefensePuzzleRow {
+    pub gameid: u32,
+    /// `GameSource::name` of the game
+    pub source: String,
+    pub size: u32,
+    pub plies_to_undo: u32,
+    /// Length of the Tinue that is threatened, not counting the defending move
+    pub tinue_depth: u32,
+    /// JSON list of the moves that escape the Tinue
+    pub saving_moves: String,
+}
+
+/// Opens an existing database
+pub fn open(db_path: &str) -> Result<Connection, TinueFinderError> {
+    Ok(Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE,
+    )?)
+}
+
+/// Opens an existing database for the commands that only read the results.
+///
+/// Tables of older versions are not migrated, the `select_` functions read missing columns as `NULL`.
+pub fn open_read_only(db_path: &str) -> Result<Connection, TinueFinderError> {
+    Ok(Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?)
+}
+
+/// Columns of the `tinues` table that were added after its first version
+const ADDED_TINUES_COLUMNS: [&str; 8] = [
+    "canonical_key",
+    "duplicate_of",
+    "tps",
+    "side_to_move",
+    "ply",
+    "played_move",
+    "tinue_tree",
+    "source",
+];
+
+/// Creates the `tinues` table, or adds the columns that are missing in tables of older versions.
+/// Only `scan` writes to the database, the other commands never migrate it.
+pub fn create_tinues_table(conn: &Connection) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tinues (
+        id integer primary key,
+        gameid integer NOT NULL REFERENCES games(id),
+        source TEXT,
+        size integer,
+        plies_to_undo integer,
+        tinue_depth integer,
+        tinue TEXT,
+        canonical_key TEXT,
+        duplicate_of integer REFERENCES tinues(id),
+        tps TEXT,
+        side_to_move TEXT,
+        ply integer,
+        played_move TEXT,
+        tinue_tree BLOB)",
+        params![],
+    )?;
+    // Tables created before the columns were introduced
+    ensure_column(conn, "tinues", "source", "TEXT")?;
+    ensure_column(conn, "tinues", "canonical_key", "TEXT")?;
+    ensure_column(
+        conn,
+        "tinues",
+        "duplicate_of",
+        "integer REFERENCES tinues(id)",
+    )?;
+    ensure_column(conn, "tinues", "tps", "TEXT")?;
+    ensure_column(conn, "tinues", "side_to_move", "TEXT")?;
+    ensure_column(conn, "tinues", "ply", "integer")?;
+    ensure_column(conn, "tinues", "played_move", "TEXT")?;
+    ensure_column(conn, "tinues", "tinue_tree", "BLOB")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS tinues_canonical_key ON tinues(canonical_key)",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Returns the names of the columns of `table`, none if the table does not exist
+fn table_columns(conn: &Connection, table: &str) -> Result<HashSet<String>, TinueFinderError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map(params![], |row| row.get(1))?
+        .collect::<Result<HashSet<String>, _>>()?;
+    Ok(columns)
+}
+
+/// `column` if it is one of `columns`, otherwise `NULL` so that it can still be selected
+fn column_or_null<'a>(columns: &HashSet<String>, column: &'a str) -> &'a str {
+    match columns.contains(column) {
+        true => column,
+        false => "NULL",
+    }
+}
+
+/// Adds `column` to `table` if it does not exist yet
+fn ensure_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), TinueFinderError> {
+    if !table_columns(conn, table)?.contains(column) {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            params![],
+        )?;
+    }
+    Ok(())
+}
+
+/// Plies without a row had no Tinue within the searched depth
+pub fn create_ply_tinues_table(conn: &Connection) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ply_tinues (
+        id integer primary key,
+        gameid integer NOT NULL REFERENCES games(id),
+        source TEXT,
+        size integer,
+        ply integer,
+        side_to_move TEXT,
+        tinue_depth integer,
+        tinue TEXT)",
+        params![],
+    )?;
+    ensure_column(conn, "ply_tinues", "source", "TEXT")?;
+    Ok(())
+}
+
+pub fn create_missed_tinues_table(conn: &Connection) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS missed_tinues (
+        id integer primary key,
+        gameid integer NOT NULL REFERENCES games(id),
+        source TEXT,
+        size integer,
+        ply integer,
+        side_to_move TEXT,
+        tinue_depth integer,
+        tinue TEXT,
+        played_move TEXT)",
+        params![],
+    )?;
+    ensure_column(conn, "missed_tinues", "source", "TEXT")?;
+    Ok(())
+}
+
+pub fn create_defense_puzzles_table(conn: &Connection) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS defense_puzzles (
+        id integer primary key,
+        gameid integer NOT NULL REFERENCES games(id),
+        source TEXT,
+        size integer,
+        plies_to_undo integer,
+        tinue_depth integer,
+        saving_moves TEXT)",
+        params![],
+    )?;
+    ensure_column(conn, "defense_puzzles", "source", "TEXT")?;
+    Ok(())
+}
+
+/// Creates the tables that record which games were scanned with which settings
+pub fn create_scan_tables(conn: &Connection) -> Result<(), TinueFinderError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS scan_runs (
+        id integer primary key,
+        started_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        finished_at TEXT,
+        source TEXT,
+        size integer,
+        mode TEXT,
+        plies_to_undo integer,
+        max_depth integer,
+        multi_tinue integer,
+        solver TEXT,
+        max_nodes integer,
+        max_time_secs integer,
+        resume_from integer);
+        CREATE TABLE IF NOT EXISTS scanned_games (
+        id integer primary key,
+        run_id integer NOT NULL REFERENCES scan_runs(id),
+        gameid integer NOT NULL,
+        outcome TEXT,
+        tinue_depth integer,
+        error TEXT,
+        stats TEXT,
+        scanned_at TEXT DEFAULT CURRENT_TIMESTAMP);
+        CREATE INDEX IF NOT EXISTS scanned_games_gameid ON scanned_games(gameid);",
+    )?;
+    ensure_column(conn, "scan_runs", "source", "TEXT")?;
+    ensure_column(conn, "scan_runs", "max_nodes", "integer")?;
+    ensure_column(conn, "scan_runs", "max_time_secs", "integer")?;
+    ensure_column(conn, "scan_runs", "resume_from", "integer")?;
+    ensure_column(conn, "scanned_games", "stats", "TEXT")?;
+    Ok(())
+}
+
+/// Inserts a new scan run and returns its id
+pub fn insert_scan_run(conn: &Connection, row: &ScanRunRow) -> Result<i64, TinueFinderError> {
+    conn.execute(
+        "INSERT INTO scan_runs(source, size, mode, plies_to_undo, max_depth, multi_tinue, solver, max_nodes, max_time_secs) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            row.source,
+            row.size,
+            row.mode,
+            row.plies_to_undo,
+            row.max_depth,
+            row.multi_tinue,
+            row.solver,
+            row.max_nodes.map(|n| n as i64),
+            row.max_time_secs
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Marks the scan run as finished. If it was interrupted, `resume_from` is the smallest id
+/// of the games that were not scanned.
+pub fn finish_scan_run(
+    conn: &Connection,
+    run_id: i64,
+    resume_from: Option<u32>,
+) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "UPDATE scan_runs SET finished_at = CURRENT_TIMESTAMP, resume_from = ? WHERE id = ?",
+        params![resume_from, run_id],
+    )?;
+    Ok(())
+}
+
+/// Records that a game was analyzed, whether a tinue was found or not
+pub fn insert_scanned_game(
+    conn: &Connection,
+    row: &ScannedGameRow,
+) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "INSERT INTO scanned_games(run_id, gameid, outcome, tinue_depth, error, stats) VALUES(?, ?, ?, ?, ?, ?)",
+        params![
+            row.run_id,
+            row.gameid,
+            row.outcome.as_str(),
+            row.tinue_depth,
+            row.error,
+            row.stats
+        ],
+    )?;
+    Ok(())
+}
+
+/// Returns the ids of the games that were scanned by any run with the settings and the source of `run`.
+///
+/// Games whose search was aborted are left out, so that they are scanned again.
+pub fn select_scanned_game_ids(
+    conn: &Connection,
+    run: &ScanRunRow,
+) -> Result<HashSet<u32>, TinueFinderError> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT scanned_games.gameid FROM scanned_games JOIN scan_runs ON scan_runs.id = scanned_games.run_id
+        WHERE IFNULL(source, ?) = ? AND size = ? AND mode = ? AND plies_to_undo = ? AND max_depth = ? AND multi_tinue = ? AND outcome != ?",
+    )?;
+    let ids = stmt
+        .query_map(
+            params![
+                PLAYTAK_SOURCE,
+                run.source,
+                run.size,
+                run.mode,
+                run.plies_to_undo,
+                run.max_depth,
+                run.multi_tinue,
+                ScanOutcome::Aborted.as_str()
+            ],
+            |row| row.get(0),
+        )?
+        .collect::<Result<HashSet<u32>, _>>()?;
+    Ok(ids)
+}
+
+/// Returns the greatest depth up to which each game of `source` is known to have no Tinue
+/// `plies_to_undo` plies before its end, so that a deeper search can skip the shallower depths
+pub fn select_no_tinue_depths(
+    conn: &Connection,
+    source: &str,
+    size: u32,
+    plies_to_undo: u32,
+) -> Result<HashMap<u32, u32>, TinueFinderError> {
+    let mut stmt = conn.prepare(
+        "SELECT scanned_games.gameid, MAX(max_depth) FROM scanned_games JOIN scan_runs ON scan_runs.id = scanned_games.run_id
+        WHERE IFNULL(source, ?) = ? AND size = ? AND mode = 'tinues' AND plies_to_undo = ? AND outcome = ?
+        GROUP BY scanned_games.gameid",
+    )?;
+    let depths = stmt
+        .query_map(
+            params![
+                PLAYTAK_SOURCE,
+                source,
+                size,
+                plies_to_undo,
+                ScanOutcome::NoTinue.as_str()
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?
+        .collect::<Result<HashMap<u32, u32>, _>>()?;
+    Ok(depths)
+}
+
+/// Returns the stored tinues, optionally only those of `board_size`
+pub fn select_tinues(
+    conn: &Connection,
+    board_size: Option<u32>,
+) -> Result<Vec<StoredTinueRow>, TinueFinderError> {
+    let columns = table_columns(conn, "tinues")?;
+    // Nothing was scanned yet
+    if columns.is_empty() {
+        return Ok(vec![]);
+    }
+    let added_columns = ADDED_TINUES_COLUMNS
+        .iter()
+        .map(|column| column_or_null(&columns, column))
+        .collect::<Vec<_>>();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, gameid, size, plies_to_undo, tinue_depth, tinue, {} FROM tinues WHERE ?1 IS NULL OR size = ?1 ORDER BY id",
+        added_columns.join(", ")
+    ))?;
+    let rows = stmt
+        .query_map(params![board_size], |row| {
+            Ok(StoredTinueRow {
+                id: row.get(0)?,
+                tinue: TinueGameRow {
+                    gameid: row.get(1)?,
+                    source: row.get(13)?,
+                    size: row.get(2)?,
+                    plies_to_undo: row.get(3)?,
+                    tinue_depth: row.get(4)?,
+                    tinue: row.get(5)?,
+                    canonical_key: row.get(6)?,
+                    tps: row.get(8)?,
+                    side_to_move: row.get(9)?,
+                    ply: row.get(10)?,
+                    played_move: row.get(11)?,
+                    tinue_tree: row.get(12)?,
+                },
+                duplicate_of: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Counts the stored tinues per board size and depth
+pub fn select_tinue_stats(conn: &Connection) -> Result<Vec<TinueStatsRow>, TinueFinderError> {
+    let columns = table_columns(conn, "tinues")?;
+    if columns.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT size, tinue_depth, COUNT(*), COUNT(DISTINCT IFNULL({}, ?) || ':' || gameid), COUNT({}) FROM tinues GROUP BY size, tinue_depth ORDER BY size, tinue_depth",
+        column_or_null(&columns, "source"),
+        column_or_null(&columns, "duplicate_of")
+    ))?;
+    let rows = stmt
+        .query_map(params![PLAYTAK_SOURCE], |row| {
+            Ok(TinueStatsRow {
+                size: row.get(0)?,
+                tinue_depth: row.get(1)?,
+                tinues: row.get(2)?,
+                games: row.get(3)?,
+                duplicates: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Inserts a tinue. If a tinue with the same canonical position is already stored,
+/// the new row is marked as its duplicate instead of being a new puzzle.
+pub fn insert_tinue(conn: &Connection, row: &TinueGameRow) -> Result<(), TinueFinderError> {
+    let duplicate_of: Option<u32> = match &row.canonical_key {
+        Some(key) => conn.query_row(
+            "SELECT MIN(id) FROM tinues WHERE canonical_key = ? AND duplicate_of IS NULL",
+            params![key],
+            |r| r.get(0),
+        )?,
+        None => None,
+    };
+    conn.execute(
+        "INSERT INTO tinues(gameid, source, size, plies_to_undo, tinue_depth, tinue, canonical_key, duplicate_of, tps, side_to_move, ply, played_move, tinue_tree) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            row.gameid,
+            row.source,
+            row.size,
+            row.plies_to_undo,
+            row.tinue_depth,
+            row.tinue,
+            row.canonical_key,
+            duplicate_of,
+            row.tps,
+            row.side_to_move,
+            row.ply,
+            row.played_move,
+            row.tinue_tree
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn insert_ply_tinue(conn: &Connection, row: &PlyTinueRow) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "INSERT INTO ply_tinues(gameid, source, size, ply, side_to_move, tinue_depth, tinue) VALUES(?, ?, ?, ?, ?, ?, ?)",
+        params![
+            row.gameid,
+            row.source,
+            row.size,
+            row.ply,
+            row.side_to_move,
+            row.tinue_depth,
+            row.tinue
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn insert_missed_tinue(
+    conn: &Connection,
+    row: &MissedTinueRow,
+) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "INSERT INTO missed_tinues(gameid, source, size, ply, side_to_move, tinue_depth, tinue, played_move) VALUES(?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            row.gameid,
+            row.source,
+            row.size,
+            row.ply,
+            row.side_to_move,
+            row.tinue_depth,
+            row.tinue,
+            row.played_move
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn insert_defense_puzzle(
+    conn: &Connection,
+    row: &DefensePuzzleRow,
+) -> Result<(), TinueFinderError> {
+    conn.execute(
+        "INSERT INTO defense_puzzles(gameid, source, size, plies_to_undo, tinue_depth, saving_moves) VALUES(?, ?, ?, ?, ?, ?)",
+        params![
+            row.gameid,
+            row.source,
+            row.size,
+            row.plies_to_undo,
+            row.tinue_depth,
+            row.saving_moves
+        ],
+    )?;
+    Ok(())
+}
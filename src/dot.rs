@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{tinuemove_to_options, TinueMove, TinueMoveOptions};
+
+/// Renders a tree of `TinueMove`s as a Graphviz DOT graph, e.g. for `dot -Tsvg`.
+///
+/// The tree is reduced with `tinuemove_to_options` first: each node holds the moves whose
+/// replies are the same. Identical subtrees are drawn once, so a node can have several parents.
+/// Attacker moves are red boxes, defender replies blue ellipses. `root_label` names the start position.
+pub fn tinue_to_dot(tinue_moves: &[TinueMove], root_label: &str) -> String {
+    let options = tinuemove_to_options(tinue_moves);
+    let mut graph = DotGraph::default();
+    for option in options.iter() {
+        let child = graph.add_node(option, true);
+        graph.edges.push(format!("    root -> n{};", child));
+    }
+
+    let mut dot = String::from("digraph tinue {\n    node [fontname=\"monospace\"];\n");
+    writeln!(
+        dot,
+        "    root [label=\"{}\", shape=plaintext];",
+        escape(root_label)
+    )
+    .unwrap();
+    for line in graph.nodes.iter().chain(graph.edges.iter()) {
+        dot.push_str(line);
+        dot.push('\n');
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Nodes and edges of the graph, identical subtrees are only added once
+#[derive(Default)]
+struct DotGraph<'a> {
+    nodes: Vec<String>,
+    edges: Vec<String>,
+    /// Index of the node of each subtree, by whether the attacker plays its moves
+    node_ids: HashMap<(bool, &'a TinueMoveOptions), usize>,
+}
+
+impl<'a> DotGraph<'a> {
+    /// Adds `option` and its replies if they are not in the graph yet, returns its index
+    fn add_node(&mut self, option: &'a TinueMoveOptions, attacker: bool) -> usize {
+        if let Some(&id) = self.node_ids.get(&(attacker, option)) {
+            return id;
+        }
+        let id = self.node_ids.len();
+        self.node_ids.insert((attacker, option), id);
+
+        let style = match attacker {
+            true => "shape=box, style=filled, fillcolor=\"#f4cccc\"",
+            false => "shape=ellipse, style=filled, fillcolor=\"#cfe2f3\"",
+        };
+        self.nodes.push(format!(
+            "    n{} [label=\"{}\", {}];",
+            id,
+            option
+                .moves
+                .iter()
+                .map(|mv| escape(mv))
+                .collect::<Vec<_>>()
+                .join("\\n"),
+            style
+        ));
+        for solution in option.solutions.iter() {
+            let child = self.add_node(solution, !attacker);
+            self.edges.push(format!("    n{} -> n{};", id, child));
+        }
+        id
+    }
+}
+
+/// Escapes a string for a quoted DOT label
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,130 @@
+use std::{error, fmt};
+
+use crate::SearchAborted;
+
+/// Everything that can go wrong while reading games and searching them for Tinues
+#[derive(Debug)]
+pub enum TinueFinderError {
+    /// A move in playtak server notation could not be parsed
+    InvalidServerNotation(String),
+    /// A move in PTN could not be parsed
+    InvalidMove {
+        mv: String,
+        reason: String,
+    },
+    /// A move was parsed but cannot be played in the position
+    IllegalMove {
+        mv: String,
+        ply: usize,
+    },
+    /// A TPS string could not be parsed
+    InvalidTps {
+        tps: String,
+        reason: String,
+    },
+    /// A game in a PTN file could not be read
+    InvalidPtn(String),
+    UnsupportedBoardSize(u32),
+    /// The game has fewer plies than should be undone
+    GameTooShort {
+        plies: usize,
+        plies_to_undo: u32,
+    },
+    /// A command line argument is missing or has an invalid value
+    InvalidArgument(String),
+    /// An encoded tree of `TinueMove`s could not be decoded
+    InvalidTinueTree(String),
+    /// A stored Tinue was written in a newer format than this program can read
+    UnsupportedFormatVersion(u32),
+    /// The search was stopped by its `SearchLimits`, so it is unknown whether there is a Tinue
+    SearchAborted(SearchAborted),
+    /// A `GameSource` failed, the games with an id of at least `next_id` were not read
+    SourceInterrupted {
+        next_id: u32,
+        reason: Box<TinueFinderError>,
+    },
+    Database(rusqlite::Error),
+    Json(serde_json::Error),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for TinueFinderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TinueFinderError::InvalidServerNotation(mv) => {
+                write!(f, "Invalid playtak move '{}'", mv)
+            }
+            TinueFinderError::InvalidMove { mv, reason } => {
+                write!(f, "Invalid move '{}': {}", mv, reason)
+            }
+            TinueFinderError::IllegalMove { mv, ply } => {
+                write!(f, "Illegal move '{}' at ply {}", mv, ply)
+            }
+            TinueFinderError::InvalidTps { tps, reason } => {
+                write!(f, "Invalid TPS '{}': {}", tps, reason)
+            }
+            TinueFinderError::InvalidPtn(reason) => write!(f, "Invalid PTN: {}", reason),
+            TinueFinderError::UnsupportedBoardSize(size) => {
+                write!(f, "Board size '{}' is not supported", size)
+            }
+            TinueFinderError::GameTooShort {
+                plies,
+                plies_to_undo,
+            } => write!(
+                f,
+                "Cannot undo {} plies of a game with {} plies",
+                plies_to_undo, plies
+            ),
+            TinueFinderError::InvalidArgument(message) => write!(f, "{}", message),
+            TinueFinderError::InvalidTinueTree(reason) => {
+                write!(f, "Invalid encoded tinue tree: {}", reason)
+            }
+            TinueFinderError::UnsupportedFormatVersion(version) => {
+                write!(f, "Tinue format version {} is not supported", version)
+            }
+            TinueFinderError::SearchAborted(reason) => write!(f, "Search aborted: {}", reason),
+            TinueFinderError::SourceInterrupted { next_id, reason } => {
+                write!(f, "Cannot read the games from #{} on: {}", next_id, reason)
+            }
+            TinueFinderError::Database(err) => write!(f, "Database error: {}", err),
+            TinueFinderError::Json(err) => write!(f, "JSON error: {}", err),
+            TinueFinderError::Io(err) => write!(f, "IO error: {}", err),
+        }
+    }
+}
+
+impl error::Error for TinueFinderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TinueFinderError::Database(err) => Some(err),
+            TinueFinderError::Json(err) => Some(err),
+            TinueFinderError::Io(err) => Some(err),
+            TinueFinderError::SourceInterrupted { reason, .. } => Some(reason.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for TinueFinderError {
+    fn from(err: rusqlite::Error) -> Self {
+        TinueFinderError::Database(err)
+    }
+}
+
+impl From<SearchAborted> for TinueFinderError {
+    fn from(reason: SearchAborted) -> Self {
+        TinueFinderError::SearchAborted(reason)
+    }
+}
+
+impl From<serde_json::Error> for TinueFinderError {
+    fn from(err: serde_json::Error) -> Self {
+        TinueFinderError::Json(err)
+    }
+}
+
+impl From<std::io::Error> for TinueFinderError {
+    fn from(err: std::io::Error) -> Self {
+        TinueFinderError::Io(err)
+    }
+}
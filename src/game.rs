@@ -0,0 +1,376 @@
+use board_game_traits::{Color, Position as PositionTrait};
+use pgn_traits::PgnPosition;
+use tiltak::position::{Move, Position};
+
+use crate::playtak::parse_server_notation;
+use crate::search::{find_saving_moves, is_on_road_to_tinue};
+use crate::{
+    tinue_search_from, IDDFSResult, Mov, SavingMoves, SearchBudget, SearchLimits, SearchStats,
+    Solver, TinueFinderError, TinueMove,
+};
+
+/// Calls the generic function `$f` with the const generic matching `$board_size`.
+///
+/// Fails with `TinueFinderError::UnsupportedBoardSize` for sizes other than 3 to 9.
+macro_rules! dispatch_board_size {
+    ($board_size:expr, $f:ident($($arg:expr),* $(,)?)) => {
+        match $board_size {
+            3 => $f::<3>($($arg),*),
+            4 => $f::<4>($($arg),*),
+            5 => $f::<5>($($arg),*),
+            6 => $f::<6>($($arg),*),
+            7 => $f::<7>($($arg),*),
+            8 => $f::<8>($($arg),*),
+            9 => $f::<9>($($arg),*),
+            board_size => Err($crate::TinueFinderError::UnsupportedBoardSize(board_size)),
+        }
+    };
+}
+pub(crate) use dispatch_board_size;
+
+/// A game read from a `GameSource`
+pub struct Game {
+    pub id: u32,
+    /// Name of the `GameSource` the game was read from, the id is only unique within it
+    pub source: String,
+    pub size: u32,
+    /// Result in PTN notation, e.g. `R-0`
+    pub result: String,
+    pub komi: Option<f64>,
+    pub player_white: Option<String>,
+    pub player_black: Option<String>,
+    pub moves: GameMoves,
+}
+
+/// The moves of a game in the notation they were read in
+pub enum GameMoves {
+    /// Comma separated playtak server notation, e.g. `P A1,M A1 A3 1 2`
+    Playtak(String),
+    /// Moves in PTN, e.g. `3a1+12`
+    Ptn(Vec<Mov>),
+}
+
+/// Parses the moves of a game, regardless of their notation
+pub fn parse_moves<const S: usize>(moves: &GameMoves) -> Result<Vec<Move>, TinueFinderError> {
+    match moves {
+        GameMoves::Playtak(server_notation) => parse_server_notation::<S>(server_notation),
+        GameMoves::Ptn(ptn_moves) => {
+            let position = Position::<S>::start_position();
+            ptn_moves
+                .iter()
+                .map(|mv| {
+                    position
+                        .move_from_san(mv)
+                        .map_err(|err| TinueFinderError::InvalidMove {
+                            mv: mv.clone(),
+                            reason: err.to_string(),
+                        })
+                })
+                .collect()
+        }
+    }
+}
+
+/// Returns an error if `mv` is not legal. `ply` is only used for the error message.
+fn check_legal_move<const S: usize>(
+    position: &Position<S>,
+    mv: &Move,
+    ply: usize,
+) -> Result<(), TinueFinderError> {
+    let mut legal_moves = vec![];
+    position.generate_moves(&mut legal_moves);
+    if !legal_moves.contains(mv) {
+        return Err(TinueFinderError::IllegalMove {
+            mv: position.move_to_san(mv),
+            ply,
+        });
+    }
+    Ok(())
+}
+
+/// Plays the first `plies` of `moves` from the start position, checking that each move is legal
+pub fn apply_moves<const S: usize>(
+    moves: &[Move],
+    plies: usize,
+) -> Result<Position<S>, TinueFinderError> {
+    let mut position = Position::<S>::start_position();
+    for (ply, mv) in moves.iter().take(plies).enumerate() {
+        check_legal_move(&position, mv, ply)?;
+        position.do_move(mv.clone());
+    }
+    Ok(position)
+}
+
+/// Number of plies played before the position `plies_to_undo` plies before the end of the game
+fn puzzle_plies(moves: &[Move], plies_to_undo: u32) -> Result<usize, TinueFinderError> {
+    moves
+        .len()
+        .checked_sub(plies_to_undo as usize)
+        .ok_or(TinueFinderError::GameTooShort {
+            plies: moves.len(),
+            plies_to_undo,
+        })
+}
+
+/// Returns the position `plies_to_undo` plies before the end of the game
+pub fn puzzle_position<const S: usize>(
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<Position<S>, TinueFinderError> {
+    let moves = parse_moves::<S>(moves)?;
+    let plies = puzzle_plies(&moves, plies_to_undo)?;
+    apply_moves::<S>(&moves, plies)
+}
+
+/// Returns the moves in PTN that lead to the position `plies_to_undo` plies before the end of the game
+pub fn puzzle_moves<const S: usize>(
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<Vec<Mov>, TinueFinderError> {
+    let moves = parse_moves::<S>(moves)?;
+    let plies = puzzle_plies(&moves, plies_to_undo)?;
+    // Checks that the moves are legal
+    apply_moves::<S>(&moves, plies)?;
+    Ok(moves[..plies]
+        .iter()
+        .map(|mv| mv.to_string::<S>())
+        .collect())
+}
+
+/// Calls `puzzle_moves` with the const generic matching `board_size`
+pub fn puzzle_moves_sized(
+    board_size: u32,
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<Vec<Mov>, TinueFinderError> {
+    dispatch_board_size!(board_size, puzzle_moves(moves, plies_to_undo))
+}
+
+/// Returns the TPS of the position `plies_to_undo` plies before the end of the game
+pub fn puzzle_tps<const S: usize>(
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<String, TinueFinderError> {
+    Ok(puzzle_position::<S>(moves, plies_to_undo)?.to_fen())
+}
+
+/// Calls `puzzle_tps` with the const generic matching `board_size`
+pub fn puzzle_tps_sized(
+    board_size: u32,
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<String, TinueFinderError> {
+    dispatch_board_size!(board_size, puzzle_tps(moves, plies_to_undo))
+}
+
+/// The position a puzzle starts from, so that it can be shown without replaying the game
+pub struct PuzzleStart {
+    pub tps: String,
+    pub side_to_move: Color,
+    /// Number of plies played before the position
+    pub ply: u32,
+    /// The move that was played from the position in the game, `None` if the game ended there
+    pub played_move: Option<Mov>,
+}
+
+/// Returns the start of the puzzle `plies_to_undo` plies before the end of the game
+pub fn puzzle_start<const S: usize>(
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<PuzzleStart, TinueFinderError> {
+    let moves = parse_moves::<S>(moves)?;
+    let plies = puzzle_plies(&moves, plies_to_undo)?;
+    let position = apply_moves::<S>(&moves, plies)?;
+    Ok(PuzzleStart {
+        tps: position.to_fen(),
+        side_to_move: position.side_to_move(),
+        ply: plies as u32,
+        played_move: moves.get(plies).map(|mv| mv.to_string::<S>()),
+    })
+}
+
+/// Calls `puzzle_start` with the const generic matching `board_size`
+pub fn puzzle_start_sized(
+    board_size: u32,
+    moves: &GameMoves,
+    plies_to_undo: u32,
+) -> Result<PuzzleStart, TinueFinderError> {
+    dispatch_board_size!(board_size, puzzle_start(moves, plies_to_undo))
+}
+
+/// Searches for a Tinue in the position `plies_to_undo` plies before the end of the game.
+///
+/// Depths below `min_depth` are skipped, pass `1` to search all of them.
+/// Fails with `TinueFinderError::SearchAborted` if the search exceeds `limits`.
+pub fn do_it<const S: usize>(
+    moves: &GameMoves,
+    plies_to_undo: u32,
+    min_depth: u32,
+    depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<(Option<IDDFSResult<Vec<TinueMove>>>, SearchStats), TinueFinderError> {
+    let mut position = puzzle_position::<S>(moves, plies_to_undo)?;
+
+    let active_color = position.side_to_move();
+
+    let mut budget = SearchBudget::new(limits);
+    let result = tinue_search_from(
+        solver,
+        &mut position,
+        min_depth,
+        depth,
+        active_color,
+        find_only_one_tinue,
+        &mut budget,
+    )?;
+    Ok((result, budget.stats()))
+}
+
+/// Calls `do_it` with the const generic matching `board_size`
+pub fn do_it_sized(
+    board_size: u32,
+    moves: &GameMoves,
+    plies_to_undo: u32,
+    min_depth: u32,
+    depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<(Option<IDDFSResult<Vec<TinueMove>>>, SearchStats), TinueFinderError> {
+    dispatch_board_size!(
+        board_size,
+        do_it(
+            moves,
+            plies_to_undo,
+            min_depth,
+            depth,
+            find_only_one_tinue,
+            solver,
+            limits
+        )
+    )
+}
+
+/// Searches for a defense puzzle in the position `plies_to_undo` plies before the end of the game:
+/// The side to move faces a Tinue of `depth` plies and at most `max_saving_moves` replies escape it.
+pub fn find_defense<const S: usize>(
+    moves: &GameMoves,
+    plies_to_undo: u32,
+    depth: u32,
+    max_saving_moves: usize,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<(Option<SavingMoves>, SearchStats), TinueFinderError> {
+    let mut position = puzzle_position::<S>(moves, plies_to_undo)?;
+
+    let attacker = !position.side_to_move();
+
+    let mut budget = SearchBudget::new(limits);
+    let saving_moves = find_saving_moves(
+        solver,
+        &mut position,
+        depth,
+        attacker,
+        max_saving_moves,
+        &mut budget,
+    )?;
+    Ok((saving_moves, budget.stats()))
+}
+
+/// Calls `find_defense` with the const generic matching `board_size`
+pub fn find_defense_sized(
+    board_size: u32,
+    moves: &GameMoves,
+    plies_to_undo: u32,
+    depth: u32,
+    max_saving_moves: usize,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<(Option<SavingMoves>, SearchStats), TinueFinderError> {
+    dispatch_board_size!(
+        board_size,
+        find_defense(
+            moves,
+            plies_to_undo,
+            depth,
+            max_saving_moves,
+            solver,
+            limits
+        )
+    )
+}
+
+/// Outcome of the search in a single position of a game
+pub struct PlyResult {
+    /// Number of plies played before the position
+    pub ply: u32,
+    pub side_to_move: Color,
+    /// Tinue of `side_to_move`, if there is one within the maximum depth
+    pub tinue: Option<IDDFSResult<Vec<TinueMove>>>,
+    /// The move that was played in the game
+    pub played_move: Mov,
+    /// `true` if there was a Tinue but `played_move` is not on any **Road to Tinue**
+    pub missed_tinue: bool,
+}
+
+/// Searches every position of the game for a Tinue of the side to move.
+///
+/// The first result with a Tinue is the moment the game was lost,
+/// unless the winning player missed it, see `PlyResult::missed_tinue`.
+pub fn scan_plies<const S: usize>(
+    moves: &GameMoves,
+    depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<(Vec<PlyResult>, SearchStats), TinueFinderError> {
+    let moves = parse_moves::<S>(moves)?;
+    let mut position = Position::<S>::start_position();
+    let mut results = Vec::with_capacity(moves.len());
+    // The limits apply to the whole game, not to each position
+    let mut budget = SearchBudget::new(limits);
+
+    for (ply, mv) in moves.iter().enumerate() {
+        check_legal_move(&position, mv, ply)?;
+        let side_to_move = position.side_to_move();
+        let tinue = tinue_search_from(
+            solver,
+            &mut position,
+            1,
+            depth,
+            side_to_move,
+            find_only_one_tinue,
+            &mut budget,
+        )?;
+        let missed_tinue = tinue.is_some()
+            && !is_on_road_to_tinue(solver, &mut position, mv, depth, side_to_move, &mut budget)?;
+        results.push(PlyResult {
+            ply: ply as u32,
+            side_to_move,
+            tinue,
+            played_move: position.move_to_san(mv),
+            missed_tinue,
+        });
+
+        position.do_move(mv.clone());
+    }
+
+    Ok((results, budget.stats()))
+}
+
+/// Calls `scan_plies` with the const generic matching `board_size`
+pub fn scan_plies_sized(
+    board_size: u32,
+    moves: &GameMoves,
+    depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+    limits: &SearchLimits,
+) -> Result<(Vec<PlyResult>, SearchStats), TinueFinderError> {
+    dispatch_board_size!(
+        board_size,
+        scan_plies(moves, depth, find_only_one_tinue, solver, limits)
+    )
+}
@@ -0,0 +1,455 @@
+use flate2::read::GzDecoder;
+use rusqlite::{params, Connection, Row, Statement};
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::{fmt, iter, thread};
+use tar::{Archive, Entry};
+use zip::ZipArchive;
+
+use crate::db;
+use crate::game::{Game, GameMoves};
+use crate::ptn::{parse_ptn_games, PtnGame};
+use crate::TinueFinderError;
+
+/// Selects the games that are searched for Tinues
+pub struct GameFilter {
+    pub board_size: u32,
+    /// Games with a smaller id are skipped
+    pub min_game_id: u32,
+}
+
+/// Name of the `SqliteGameSource`, also assumed for results stored before sources were recorded
+pub const PLAYTAK_SOURCE: &str = "playtak";
+
+/// Number of games read from the database at once
+const PAGE_SIZE: u32 = 1000;
+
+/// Games, each item is either a game or the reason why it could not be read
+pub type Games<'a> = Box<dyn Iterator<Item = Result<Game, TinueFinderError>> + 'a>;
+
+/// Somewhere to read games from, can be moved to a reader thread
+pub trait GameSource: Send {
+    /// Returns the games matching `filter` that ended in a road win.
+    ///
+    /// Games that cannot be read are returned as errors so that the caller can skip them.
+    /// If the source itself fails, the last item is `TinueFinderError::SourceInterrupted`.
+    fn road_wins(&self, filter: &GameFilter) -> Result<Games<'_>, TinueFinderError>;
+
+    /// Stored with the results, so that the ids of games from different sources don't collide
+    fn name(&self) -> String;
+}
+
+fn is_road_win(result: &str) -> bool {
+    result == "R-0" || result == "0-R"
+}
+
+/// The `games` table of a playtak database
+pub struct SqliteGameSource {
+    conn: Connection,
+}
+
+impl SqliteGameSource {
+    pub fn open(db_path: &str) -> Result<Self, TinueFinderError> {
+        Ok(SqliteGameSource {
+            conn: db::open_read_only(db_path)?,
+        })
+    }
+}
+
+impl GameSource for SqliteGameSource {
+    /// Reads the games in pages ordered by id, so that only a single page is held in memory
+    fn road_wins(&self, filter: &GameFilter) -> Result<Games<'_>, TinueFinderError> {
+        let mut stmt = self.conn.prepare("SELECT id, notation, result, size, player_white, player_black FROM games WHERE (result = ? or result = ?) and id >= ? AND size = ? ORDER BY id LIMIT ?")?;
+
+        let board_size = filter.board_size;
+        let mut next_id = filter.min_game_id;
+        let mut page = VecDeque::new();
+        let mut last_page = false;
+        Ok(Box::new(iter::from_fn(move || {
+            if page.is_empty() && !last_page {
+                match read_page(&mut stmt, board_size, next_id) {
+                    Ok(games) => {
+                        last_page = games.len() < PAGE_SIZE as usize;
+                        if let Some((id, _)) = games.last() {
+                            next_id = id + 1;
+                        }
+                        page.extend(games.into_iter().map(|(_, game)| game));
+                    }
+                    Err(err) => {
+                        last_page = true;
+                        return Some(Err(TinueFinderError::SourceInterrupted {
+                            next_id,
+                            reason: Box::new(err),
+                        }));
+                    }
+                }
+            }
+            page.pop_front()
+        })))
+    }
+
+    fn name(&self) -> String {
+        PLAYTAK_SOURCE.to_string()
+    }
+}
+
+/// Reads up to `PAGE_SIZE` road wins with an id of at least `first_id`, together with their ids
+fn read_page(
+    stmt: &mut Statement,
+    board_size: u32,
+    first_id: u32,
+) -> Result<Vec<(u32, Result<Game, TinueFinderError>)>, TinueFinderError> {
+    let games = stmt
+        .query_map(
+            params!["R-0", "0-R", first_id, board_size, PAGE_SIZE],
+            |row| {
+                let id: u32 = row.get(0)?;
+                Ok((id, game_from_row(id, row).map_err(TinueFinderError::from)))
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(games)
+}
+
+fn game_from_row(id: u32, row: &Row) -> rusqlite::Result<Game> {
+    Ok(Game {
+        id,
+        source: PLAYTAK_SOURCE.to_string(),
+        moves: GameMoves::Playtak(row.get(1)?),
+        result: row.get(2)?,
+        size: row.get(3)?,
+        komi: None,
+        player_white: row.get(4)?,
+        player_black: row.get(5)?,
+    })
+}
+
+/// A single PTN file, which may contain several games
+pub struct PtnFileSource {
+    path: PathBuf,
+}
+
+impl PtnFileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        PtnFileSource { path: path.into() }
+    }
+}
+
+impl GameSource for PtnFileSource {
+    fn road_wins(&self, filter: &GameFilter) -> Result<Games<'_>, TinueFinderError> {
+        let text = fs::read_to_string(&self.path)?;
+        let source = self.name();
+        let games = parse_ptn_games(&text)
+            .into_iter()
+            .enumerate()
+            .map(|(i, ptn)| ptn_to_game(ptn, i as u32 + 1, &source))
+            .collect::<Vec<_>>();
+        Ok(filter_ptn_games(games.into_iter(), filter))
+    }
+
+    fn name(&self) -> String {
+        ptn_source_name(&self.path)
+    }
+}
+
+/// All `.ptn` files in a directory and its subdirectories, read in alphabetical order
+pub struct PtnDirectorySource {
+    path: PathBuf,
+}
+
+impl PtnDirectorySource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        PtnDirectorySource { path: path.into() }
+    }
+}
+
+impl GameSource for PtnDirectorySource {
+    /// Reads the files one at a time, so that only the games of a single file are held in memory
+    fn road_wins(&self, filter: &GameFilter) -> Result<Games<'_>, TinueFinderError> {
+        let mut files = vec![];
+        collect_ptn_files(&self.path, &mut files)?;
+        files.sort();
+
+        let root = self.path.clone();
+        let files = files.into_iter().map(move |file| {
+            let text = fs::read_to_string(&file).map_err(|err| {
+                TinueFinderError::InvalidPtn(format!("Cannot read {}: {}", file.display(), err))
+            });
+            (relative_name(&root, &file), text)
+        });
+        Ok(filter_ptn_games(
+            ptn_files_to_games(files, self.name()),
+            filter,
+        ))
+    }
+
+    fn name(&self) -> String {
+        ptn_source_name(&self.path)
+    }
+}
+
+/// The `.ptn` files in a zip archive, read one at a time in alphabetical order
+pub struct PtnZipSource {
+    path: PathBuf,
+}
+
+impl PtnZipSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        PtnZipSource { path: path.into() }
+    }
+}
+
+impl GameSource for PtnZipSource {
+    fn road_wins(&self, filter: &GameFilter) -> Result<Games<'_>, TinueFinderError> {
+        let mut archive = ZipArchive::new(File::open(&self.path)?).map_err(archive_error)?;
+        let mut entries = vec![];
+        for index in 0..archive.len() {
+            let name = archive
+                .by_index(index)
+                .map_err(archive_error)?
+                .name()
+                .to_string();
+            if is_ptn_file(Path::new(&name)) {
+                entries.push((name, index));
+            }
+        }
+        entries.sort();
+
+        let files = entries.into_iter().map(move |(name, index)| {
+            let text = archive
+                .by_index(index)
+                .map_err(archive_error)
+                .and_then(|mut file| {
+                    let mut text = String::new();
+                    file.read_to_string(&mut text)?;
+                    Ok(text)
+                });
+            (name, text)
+        });
+        Ok(filter_ptn_games(
+            ptn_files_to_games(files, self.name()),
+            filter,
+        ))
+    }
+
+    fn name(&self) -> String {
+        ptn_source_name(&self.path)
+    }
+}
+
+/// The `.ptn` files in a tar archive, which may be compressed with gzip.
+///
+/// A tar archive can only be read from the start, so the files are read in the order of the archive.
+/// They are read one at a time on a thread of their own, which stops when the games are dropped.
+pub struct PtnTarSource {
+    path: PathBuf,
+}
+
+impl PtnTarSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        PtnTarSource { path: path.into() }
+    }
+}
+
+impl GameSource for PtnTarSource {
+    fn road_wins(&self, filter: &GameFilter) -> Result<Games<'_>, TinueFinderError> {
+        let file = File::open(&self.path)?;
+        let reader: Box<dyn Read + Send> = match is_gzip_file(&self.path) {
+            true => Box::new(GzDecoder::new(file)),
+            false => Box::new(file),
+        };
+        let archive_name = self.path.display().to_string();
+
+        let (sender, receiver) = mpsc::sync_channel(0);
+        thread::spawn(move || {
+            let mut archive = Archive::new(reader);
+            let entries = match archive.entries() {
+                Ok(entries) => entries,
+                Err(err) => {
+                    let _ = sender.send((archive_name, Err(archive_error(err))));
+                    return;
+                }
+            };
+            for entry in entries {
+                let file = match entry.and_then(read_tar_entry) {
+                    Ok(Some((name, text))) => (name, Ok(text)),
+                    Ok(None) => continue,
+                    // The rest of the archive cannot be read either
+                    Err(err) => (archive_name.clone(), Err(archive_error(err))),
+                };
+                let failed = file.1.is_err();
+                if sender.send(file).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Ok(filter_ptn_games(
+            ptn_files_to_games(receiver.into_iter(), self.name()),
+            filter,
+        ))
+    }
+
+    fn name(&self) -> String {
+        ptn_source_name(&self.path)
+    }
+}
+
+/// The name and the text of a `.ptn` file in a tar archive, `None` for other entries
+fn read_tar_entry<R: Read>(mut entry: Entry<'_, R>) -> io::Result<Option<(String, String)>> {
+    let name = entry.path()?.to_string_lossy().into_owned();
+    if !entry.header().entry_type().is_file() || !is_ptn_file(Path::new(&name)) {
+        return Ok(None);
+    }
+    let mut text = String::new();
+    entry.read_to_string(&mut text)?;
+    Ok(Some((name, text)))
+}
+
+fn archive_error(err: impl fmt::Display) -> TinueFinderError {
+    TinueFinderError::InvalidPtn(format!("Cannot read the archive: {}", err))
+}
+
+/// The games of PTN files, given by their name within the source and their text.
+///
+/// Games without an `Id` tag get an id derived from the name of their file and their position in it,
+/// so that the ids of the other games don't change when files are added, removed or fixed.
+fn ptn_files_to_games<'a>(
+    files: impl Iterator<Item = (String, Result<String, TinueFinderError>)> + 'a,
+    source: String,
+) -> impl Iterator<Item = Result<Game, TinueFinderError>> + 'a {
+    let mut used_ids = HashSet::new();
+    files.flat_map(move |(name, text)| {
+        let games: Vec<Result<Game, TinueFinderError>> = match text {
+            Ok(text) => parse_ptn_games(&text)
+                .into_iter()
+                .enumerate()
+                .map(|(index, ptn)| {
+                    let id = match ptn_id(&ptn) {
+                        Some(id) => {
+                            used_ids.insert(id);
+                            id
+                        }
+                        None => fallback_id(&name, index, &mut used_ids),
+                    };
+                    ptn_to_game(ptn, id, &source)
+                })
+                .collect(),
+            Err(err) => vec![Err(err)],
+        };
+        games
+    })
+}
+
+/// Id of the game at `index` in the file `name` that has no `Id` tag.
+///
+/// A hash of both, FNV-1a because the hasher of the standard library may change between Rust versions.
+/// If the id was already given to another game of the source, the next free id is taken.
+fn fallback_id(name: &str, index: usize, used_ids: &mut HashSet<u32>) -> u32 {
+    let mut id = format!("{}#{}", name, index)
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        });
+    while !used_ids.insert(id) {
+        id = id.wrapping_add(1);
+    }
+    id
+}
+
+/// The path of `file` within `root` with `/` as separator, so that it is the same on every platform
+fn relative_name(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `ptn:` followed by the absolute path, so that the name does not depend on the working directory
+fn ptn_source_name(path: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    format!("ptn:{}", path.display())
+}
+
+/// Returns a source for a PTN file, a directory of PTN files or a zip or tar archive of them
+pub fn open_ptn_source(path: &str) -> Box<dyn GameSource> {
+    let name = path.to_ascii_lowercase();
+    if Path::new(path).is_dir() {
+        Box::new(PtnDirectorySource::new(path))
+    } else if name.ends_with(".zip") {
+        Box::new(PtnZipSource::new(path))
+    } else if name.ends_with(".tar") || is_gzip_file(Path::new(path)) {
+        Box::new(PtnTarSource::new(path))
+    } else {
+        Box::new(PtnFileSource::new(path))
+    }
+}
+
+/// `.tar.gz` or `.tgz`
+fn is_gzip_file(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_ascii_lowercase();
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+fn is_ptn_file(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("ptn"))
+}
+
+fn collect_ptn_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), TinueFinderError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_ptn_files(&path, files)?;
+        } else if is_ptn_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn filter_ptn_games<'a>(
+    games: impl Iterator<Item = Result<Game, TinueFinderError>> + 'a,
+    filter: &GameFilter,
+) -> Games<'a> {
+    let board_size = filter.board_size;
+    let min_game_id = filter.min_game_id;
+    Box::new(games.filter(move |game| match game {
+        Ok(game) => game.size == board_size && game.id >= min_game_id && is_road_win(&game.result),
+        Err(_) => true,
+    }))
+}
+
+/// The `Id` tag of a PTN game
+fn ptn_id(ptn: &PtnGame) -> Option<u32> {
+    ptn.tag("Id").and_then(|id| id.parse::<u32>().ok())
+}
+
+/// Converts the tags and moves of a PTN game. `default_id` is used if the game has no `Id` tag.
+fn ptn_to_game(ptn: PtnGame, default_id: u32, source: &str) -> Result<Game, TinueFinderError> {
+    if ptn.tag("TPS").is_some() {
+        return Err(TinueFinderError::InvalidPtn(
+            "games that don't start with an empty board are not supported".to_string(),
+        ));
+    }
+    let size = ptn
+        .tag("Size")
+        .and_then(|size| size.parse::<u32>().ok())
+        .ok_or_else(|| TinueFinderError::InvalidPtn("missing or invalid Size tag".to_string()))?;
+    let id = ptn_id(&ptn).unwrap_or(default_id);
+
+    Ok(Game {
+        id,
+        source: source.to_string(),
+        size,
+        result: ptn.tag("Result").unwrap_or_default().to_string(),
+        komi: ptn.tag("Komi").and_then(|komi| komi.parse::<f64>().ok()),
+        player_white: ptn.tag("Player1").map(str::to_string),
+        player_black: ptn.tag("Player2").map(str::to_string),
+        moves: GameMoves::Ptn(ptn.moves),
+    })
+}
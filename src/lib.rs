@@ -0,0 +1,38 @@
+#![feature(slice_group_by)]
+//! Searches Tak positions for **Roads to Tinue**.
+//!
+//! The solvers are in `search` (IDDFS) and `pns` (proof-number search),
+//! `serialize` reduces their results to the formats stored in the database,
+//! `compact` encodes complete trees of them in a few bytes and `dot` draws them as graphs.
+
+pub mod compact;
+pub mod db;
+pub mod dot;
+mod error;
+pub mod game;
+pub mod game_source;
+pub mod limits;
+pub mod playtak;
+pub mod pns;
+pub mod ptn;
+pub mod search;
+pub mod serialize;
+pub mod stats;
+pub mod symmetry;
+pub mod tps;
+pub mod transposition_table;
+
+pub use error::TinueFinderError;
+pub use limits::{SearchAborted, SearchBudget, SearchLimits};
+pub use search::{
+    iddf_tinue_search, iddf_tinue_search_from, tinue_search, tinue_search_from, IDDFSResult,
+    SavingMoves, Solver, TinueMove,
+};
+pub use serialize::{
+    get_longest_sequence, move_list_to_vec, options_to_tinuemoves, tinuemove_to_options, Mov,
+    MoveListNode, TinueMoveOptions, TinueSolution, VersionedTinue,
+};
+pub use stats::SearchStats;
+
+#[cfg(test)]
+mod tests;
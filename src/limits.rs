@@ -0,0 +1,110 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::SearchStats;
+
+/// How often the clock and the cancel flag are checked, in visited nodes
+const CHECK_INTERVAL: u64 = 1024;
+
+/// Limits of a search, so that a single position cannot block a thread for hours
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    /// Maximum number of positions to visit
+    pub max_nodes: Option<u64>,
+    pub max_time: Option<Duration>,
+    /// Maximum number of nodes proof-number search keeps in memory
+    pub max_tree_nodes: Option<u64>,
+    /// Set to `true` to stop all searches that share the flag
+    pub cancel: Arc<AtomicBool>,
+}
+
+impl SearchLimits {
+    /// No limits, only the cancel flag of the returned value can stop the search
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+}
+
+/// Why a search was stopped before it could prove or disprove a Tinue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchAborted {
+    NodeLimit,
+    TimeLimit,
+    /// The tree of proof-number search grew too large
+    MemoryLimit,
+    Cancelled,
+}
+
+impl fmt::Display for SearchAborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchAborted::NodeLimit => write!(f, "node limit reached"),
+            SearchAborted::TimeLimit => write!(f, "time limit reached"),
+            SearchAborted::MemoryLimit => write!(f, "memory limit reached"),
+            SearchAborted::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// Keeps track of the nodes and the time used by the searches of a single game
+#[derive(Debug)]
+pub struct SearchBudget {
+    limits: SearchLimits,
+    started: Instant,
+    /// Collected by the solvers while they search
+    pub(crate) stats: SearchStats,
+}
+
+impl SearchBudget {
+    /// Starts the clock
+    pub fn new(limits: &SearchLimits) -> Self {
+        SearchBudget {
+            limits: limits.clone(),
+            started: Instant::now(),
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// Number of nodes visited so far
+    pub fn nodes(&self) -> u64 {
+        self.stats.nodes
+    }
+
+    /// Statistics of the searches so far, including the time since the budget was created
+    pub fn stats(&self) -> SearchStats {
+        SearchStats {
+            time: self.started.elapsed(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// Counts a visited node and returns an error if the search has to stop
+    pub fn visit(&mut self) -> Result<(), SearchAborted> {
+        self.stats.nodes += 1;
+        let nodes = self.stats.nodes;
+        if matches!(self.limits.max_nodes, Some(max_nodes) if nodes > max_nodes) {
+            return Err(SearchAborted::NodeLimit);
+        }
+        if nodes % CHECK_INTERVAL == 0 {
+            if self.limits.cancel.load(Ordering::Relaxed) {
+                return Err(SearchAborted::Cancelled);
+            }
+            if matches!(self.limits.max_time, Some(max_time) if self.started.elapsed() > max_time) {
+                return Err(SearchAborted::TimeLimit);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error if a search tree of `nodes` nodes exceeds the memory limit
+    pub fn check_tree_size(&self, nodes: usize) -> Result<(), SearchAborted> {
+        match self.limits.max_tree_nodes {
+            Some(max_tree_nodes) if nodes as u64 > max_tree_nodes => {
+                Err(SearchAborted::MemoryLimit)
+            }
+            _ => Ok(()),
+        }
+    }
+}
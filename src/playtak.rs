@@ -0,0 +1,83 @@
+use board_game_traits::Position as PositionTrait;
+use pgn_traits::PgnPosition;
+use tiltak::position::{Move, Position};
+
+use crate::TinueFinderError;
+
+/// Parses the comma separated move list used by the playtak server, e.g. `P A1,M A1 A3 1 2`
+pub fn parse_server_notation<const S: usize>(
+    server_notation: &str,
+) -> Result<Vec<Move>, TinueFinderError> {
+    let position = Position::<S>::start_position();
+    server_notation
+        .split(',')
+        .map(|server_move| {
+            let ptn = server_move_to_ptn(server_move.trim(), S)?;
+            position
+                .move_from_san(&ptn)
+                .map_err(|err| TinueFinderError::InvalidMove {
+                    mv: ptn,
+                    reason: err.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Converts a single move in playtak server notation to PTN, e.g. `M A1 A3 1 2` to `3a1+12`.
+///
+/// Validates the move against the board `size` so that malformed moves don't reach tiltak.
+fn server_move_to_ptn(server_move: &str, size: usize) -> Result<String, TinueFinderError> {
+    let invalid = || TinueFinderError::InvalidServerNotation(server_move.to_string());
+    let parse_square = |square: &str| -> Result<(u8, u8), TinueFinderError> {
+        let mut chars = square.chars();
+        let file = chars.next().ok_or_else(invalid)?.to_ascii_lowercase();
+        if !file.is_ascii_lowercase() {
+            return Err(invalid());
+        }
+        let file = file as u8 - b'a';
+        let rank = chars.as_str().parse::<u8>().map_err(|_| invalid())?;
+        if file as usize >= size || rank == 0 || rank as usize > size {
+            return Err(invalid());
+        }
+        Ok((file, rank - 1))
+    };
+    let square_name = |(file, rank): (u8, u8)| format!("{}{}", (b'a' + file) as char, rank + 1);
+
+    let words: Vec<&str> = server_move.split_whitespace().collect();
+    match words.as_slice() {
+        ["P", square] => Ok(square_name(parse_square(square)?)),
+        ["P", square, "C"] => Ok(format!("C{}", square_name(parse_square(square)?))),
+        ["P", square, "W"] => Ok(format!("S{}", square_name(parse_square(square)?))),
+        ["M", from, to, drops @ ..] if !drops.is_empty() => {
+            let from = parse_square(from)?;
+            let to = parse_square(to)?;
+            let drops = drops
+                .iter()
+                .map(|drop| drop.parse::<usize>().ok().filter(|&d| d > 0))
+                .collect::<Option<Vec<usize>>>()
+                .ok_or_else(invalid)?;
+
+            let (direction, distance) = match (from, to) {
+                ((f1, r1), (f2, r2)) if f1 == f2 && r2 > r1 => ('+', r2 - r1),
+                ((f1, r1), (f2, r2)) if f1 == f2 && r2 < r1 => ('-', r1 - r2),
+                ((f1, r1), (f2, r2)) if r1 == r2 && f2 > f1 => ('>', f2 - f1),
+                ((f1, r1), (f2, r2)) if r1 == r2 && f2 < f1 => ('<', f1 - f2),
+                _ => return Err(invalid()),
+            };
+            let pieces: usize = drops.iter().sum();
+            if distance as usize != drops.len() || pieces > size {
+                return Err(invalid());
+            }
+
+            let drops: String = drops.iter().map(|d| d.to_string()).collect();
+            Ok(format!(
+                "{}{}{}{}",
+                pieces,
+                square_name(from),
+                direction,
+                drops
+            ))
+        }
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,347 @@
+use board_game_traits::{Color, Position as PositionTrait};
+use pgn_traits::PgnPosition;
+use tiltak::position::{Move, Position};
+
+use crate::limits::{SearchAborted, SearchBudget, SearchLimits};
+use crate::search::{is_skipped_wall, is_win_for, ordered_moves};
+use crate::{IDDFSResult, TinueMove};
+
+/// Proof or disproof number of a node that can never be proven or disproven
+const INFINITY: u32 = u32::MAX;
+const ROOT: usize = 0;
+
+/// A node of the proof-number search tree.
+///
+/// Nodes where `me` is to move are OR nodes (one winning move suffices),
+/// nodes where the opponent is to move are AND nodes (every reply must lose).
+struct Node {
+    /// Move leading from the parent to this node, `None` for the root
+    mv: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    expanded: bool,
+    my_turn: bool,
+    /// Number of plies left to finish the Tinue
+    depth: u32,
+    proof: u32,
+    disproof: u32,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, parent: Option<usize>, my_turn: bool, depth: u32) -> Self {
+        Node {
+            mv,
+            parent,
+            children: vec![],
+            expanded: false,
+            my_turn,
+            depth,
+            proof: 1,
+            disproof: 1,
+        }
+    }
+
+    fn is_proven(&self) -> bool {
+        self.proof == 0
+    }
+
+    fn is_solved(&self) -> bool {
+        self.proof == 0 || self.disproof == 0
+    }
+}
+
+struct ProofTree {
+    nodes: Vec<Node>,
+}
+
+/// Searches for a **Road to Tinue** of at most `max_depth` plies using proof-number search.
+///
+/// Returns the same as `iddf_tinue_search`: `depth` is the length of the shortest Tinue.
+/// The longest line of a proof is only an upper bound, so shorter depths are searched
+/// until one is disproven. Unlike IDDFS the search is guided by the number of moves that still need to be proven,
+/// which finds long Tinues with narrow defences a lot quicker.
+pub fn pns_tinue_search<const S: usize>(
+    position: &mut Position<S>,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+) -> Option<IDDFSResult<Vec<TinueMove>>> {
+    let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+    pns_tinue_search_limited(position, max_depth, me, find_only_one_tinue, &mut budget)
+        .expect("unlimited searches are never aborted")
+}
+
+/// Like `pns_tinue_search`, but stops when `budget` is used up. Every expanded node counts as visited.
+pub fn pns_tinue_search_limited<const S: usize>(
+    position: &mut Position<S>,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    budget: &mut SearchBudget,
+) -> Result<Option<IDDFSResult<Vec<TinueMove>>>, SearchAborted> {
+    let mut result = match prove(position, max_depth, me, find_only_one_tinue, budget)? {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let mut depth = solution_depth(&result);
+    while depth > 2 {
+        match prove(position, depth - 2, me, find_only_one_tinue, budget)? {
+            Some(shorter) => {
+                depth = solution_depth(&shorter);
+                result = shorter;
+            }
+            None => break,
+        }
+    }
+    Ok(Some(IDDFSResult { depth, result }))
+}
+
+/// Returns the **Roads to Tinue** of a single proof-number search, `None` if there is no Tinue within `max_depth`
+fn prove<const S: usize>(
+    position: &mut Position<S>,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    budget: &mut SearchBudget,
+) -> Result<Option<Vec<TinueMove>>, SearchAborted> {
+    if max_depth == 0 {
+        return Ok(None);
+    }
+    let mut tree = ProofTree {
+        nodes: vec![Node::new(
+            None,
+            None,
+            position.side_to_move() == me,
+            max_depth,
+        )],
+    };
+
+    tree.solve(position, ROOT, me, budget)?;
+    if !tree.nodes[ROOT].is_proven() {
+        return Ok(None);
+    }
+
+    Ok(Some(tree.solution(
+        position,
+        ROOT,
+        me,
+        find_only_one_tinue,
+        budget,
+    )?))
+}
+
+/// Length of the longest line in `tinue_moves`
+fn solution_depth(tinue_moves: &[TinueMove]) -> u32 {
+    tinue_moves
+        .iter()
+        .map(|tm| 1 + tm.next.as_ref().map_or(0, |next| solution_depth(next)))
+        .max()
+        .unwrap_or(0)
+}
+
+impl ProofTree {
+    /// Runs proof-number search until the node `start` is proven or disproven.
+    ///
+    /// `position` must be the position of `start`. If the search is aborted, `position` is restored.
+    fn solve<const S: usize>(
+        &mut self,
+        position: &mut Position<S>,
+        start: usize,
+        me: Color,
+        budget: &mut SearchBudget,
+    ) -> Result<(), SearchAborted> {
+        while !self.nodes[start].is_solved() {
+            // Select the most proving node
+            let mut reverse_moves = vec![];
+            let mut current = start;
+            while self.nodes[current].expanded {
+                let node = &self.nodes[current];
+                let next = if node.my_turn {
+                    node.children.iter().min_by_key(|&&c| self.nodes[c].proof)
+                } else {
+                    node.children.iter().min_by_key(|&&c| self.nodes[c].disproof)
+                };
+                let next = *next.expect("unsolved expanded nodes have children");
+                let mv = self.nodes[next].mv.clone().unwrap();
+                reverse_moves.push(position.do_move(mv));
+                current = next;
+            }
+
+            if let Err(aborted) = budget
+                .visit()
+                .and_then(|()| budget.check_tree_size(self.nodes.len()))
+            {
+                for reverse_move in reverse_moves.into_iter().rev() {
+                    position.reverse_move(reverse_move);
+                }
+                return Err(aborted);
+            }
+            self.expand(position, current, me);
+            let node = &self.nodes[current];
+            budget.stats.record_node(node.my_turn);
+            budget
+                .stats
+                .record_children(node.my_turn, node.children.len() as u64);
+
+            // Propagate the new numbers back up
+            let mut node = current;
+            loop {
+                self.update(node);
+                if node == start {
+                    break;
+                }
+                node = self.nodes[node].parent.unwrap();
+            }
+
+            for reverse_move in reverse_moves.into_iter().rev() {
+                position.reverse_move(reverse_move);
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the children of `index` and sets their proof and disproof numbers
+    fn expand<const S: usize>(&mut self, position: &mut Position<S>, index: usize, me: Color) {
+        let my_turn = self.nodes[index].my_turn;
+        let depth = self.nodes[index].depth;
+        self.nodes[index].expanded = true;
+
+        // Children are appended to the arena, so an early return can drop them again
+        let first_child = self.nodes.len();
+        let mut children = vec![];
+        for mv in ordered_moves(position) {
+            if is_skipped_wall(&mv, my_turn, depth) {
+                continue;
+            }
+
+            let reverse_move = position.do_move(mv.clone());
+            let game_result = position.game_result();
+            position.reverse_move(reverse_move);
+
+            let (proof, disproof) = match game_result {
+                Some(result) if is_win_for(result, me) => {
+                    if !my_turn {
+                        // Win for me, but given to me by the opponent
+                        continue;
+                    }
+                    (0, INFINITY)
+                }
+                Some(_) => {
+                    if !my_turn {
+                        // Early loss or draw: the opponent escapes the Tinue
+                        self.nodes.truncate(first_child);
+                        self.nodes[index].proof = INFINITY;
+                        self.nodes[index].disproof = 0;
+                        return;
+                    }
+                    (INFINITY, 0)
+                }
+                // No plies left to finish the road
+                None if depth <= 1 => (INFINITY, 0),
+                None => (1, 1),
+            };
+
+            let mut child = Node::new(Some(mv), Some(index), !my_turn, depth.saturating_sub(1));
+            child.proof = proof;
+            child.disproof = disproof;
+            child.expanded = child.is_solved();
+            children.push(self.nodes.len());
+            self.nodes.push(child);
+        }
+
+        if children.is_empty() {
+            // No winning move, or every reply of the opponent hands me the win.
+            // IDDFS has no **Road to Tinue** to return in the latter case either,
+            // so it counts as disproven to give the same result as `iddf_tinue_search`.
+            let node = &mut self.nodes[index];
+            node.proof = INFINITY;
+            node.disproof = 0;
+        }
+        self.nodes[index].children = children;
+    }
+
+    /// Recomputes the proof and disproof number of `index` from its children
+    fn update(&mut self, index: usize) {
+        let node = &self.nodes[index];
+        if node.children.is_empty() {
+            // Leaves keep the numbers set when they were expanded
+            return;
+        }
+
+        let proofs = node.children.iter().map(|&c| self.nodes[c].proof);
+        let disproofs = node.children.iter().map(|&c| self.nodes[c].disproof);
+        let (proof, disproof) = if node.my_turn {
+            (proofs.min().unwrap(), disproofs.fold(0, u32::saturating_add))
+        } else {
+            (proofs.fold(0, u32::saturating_add), disproofs.min().unwrap())
+        };
+
+        let node = &mut self.nodes[index];
+        node.proof = proof;
+        node.disproof = disproof;
+    }
+
+    /// Collects the **Roads to Tinue** of the proven node `index`.
+    ///
+    /// Unless `find_only_one_tinue` is set, unsolved alternatives of `me` are solved first
+    /// so that every winning move ends up in the result.
+    fn solution<const S: usize>(
+        &mut self,
+        position: &mut Position<S>,
+        index: usize,
+        me: Color,
+        find_only_one_tinue: bool,
+        budget: &mut SearchBudget,
+    ) -> Result<Vec<TinueMove>, SearchAborted> {
+        let mut tinue_moves = vec![];
+        let my_turn = self.nodes[index].my_turn;
+
+        for child in self.nodes[index].children.clone() {
+            let mv = self.nodes[child].mv.clone().unwrap();
+            let san = position.move_to_san(&mv);
+            let reverse_move = position.do_move(mv);
+
+            let result = self.child_solution(position, child, me, find_only_one_tinue, budget);
+            position.reverse_move(reverse_move);
+            if let Some(next) = result? {
+                tinue_moves.push(TinueMove { mv: san, next });
+            }
+
+            if my_turn && find_only_one_tinue && !tinue_moves.is_empty() {
+                break;
+            }
+        }
+
+        Ok(tinue_moves)
+    }
+
+    /// Returns the replies of the proven node `child` as `TinueMove::next`, or `None` if `child`
+    /// is not proven. `position` must be the position of `child`.
+    fn child_solution<const S: usize>(
+        &mut self,
+        position: &mut Position<S>,
+        child: usize,
+        me: Color,
+        find_only_one_tinue: bool,
+        budget: &mut SearchBudget,
+    ) -> Result<Option<Option<Vec<TinueMove>>>, SearchAborted> {
+        // `child` is an alternative of `me` that may not be solved yet
+        if !self.nodes[child].my_turn && !find_only_one_tinue {
+            self.solve(position, child, me, budget)?;
+        }
+
+        if !self.nodes[child].is_proven() {
+            return Ok(None);
+        }
+        if self.nodes[child].children.is_empty() {
+            return Ok(Some(None));
+        }
+        Ok(Some(Some(self.solution(
+            position,
+            child,
+            me,
+            find_only_one_tinue,
+            budget,
+        )?)))
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{get_longest_sequence, Mov, TinueMove};
+
+/// Writes `moves` as a PTN game that starts at the position given by `tps`
+pub fn ptn_from_tps(tps: &str, size: u32, moves: &[Mov]) -> String {
+    let mut fields = tps.split_whitespace().skip(1);
+    let white_to_move = fields.next() != Some("2");
+    let move_number = fields
+        .next()
+        .and_then(|number| number.parse::<u32>().ok())
+        .unwrap_or(1);
+
+    format!(
+        "[Size \"{}\"]\n[TPS \"{}\"]\n\n{}\n",
+        size,
+        tps,
+        ptn_move_text(move_number, white_to_move, moves)
+    )
+}
+
+/// Numbers `moves` with one full move per line, e.g. `12. a1 b2`.
+///
+/// If black is to move first, white's ply is written as `--`.
+pub fn ptn_move_text(move_number: u32, white_to_move: bool, moves: &[Mov]) -> String {
+    let mut plies: Vec<&str> = vec![];
+    if !white_to_move {
+        plies.push("--");
+    }
+    plies.extend(moves.iter().map(|mv| mv.as_str()));
+
+    plies
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| format!("{}. {}", move_number + i as u32, pair.join(" ")))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// A ply of an annotated line, with the lines that could be played instead of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtnPly {
+    pub mv: Mov,
+    /// Written as `{comment}` after the move
+    pub comment: Option<String>,
+    /// Lines that start with an alternative to `mv`, written as `( ... )` after it
+    pub variations: Vec<Vec<PtnPly>>,
+}
+
+impl PtnPly {
+    pub fn new(mv: Mov) -> Self {
+        PtnPly {
+            mv,
+            comment: None,
+            variations: vec![],
+        }
+    }
+}
+
+/// Turns a Tinue into an annotated line.
+///
+/// The main line is the longest **Road to Tinue** of the first move, as in `get_longest_sequence`.
+/// Every other defense is added as a variation of the defense played in the main line,
+/// each of them continued with its own longest line and its own alternative defenses.
+pub fn tinue_line(tinue_moves: &[TinueMove]) -> Vec<PtnPly> {
+    match tinue_moves.first() {
+        Some(first) => attack_line(first),
+        None => vec![],
+    }
+}
+
+/// The line starting with `attack`, where the defenses are the variations
+fn attack_line(attack: &TinueMove) -> Vec<PtnPly> {
+    let mut line = vec![PtnPly::new(attack.mv.clone())];
+    let defenses = match &attack.next {
+        Some(defenses) if !defenses.is_empty() => defenses,
+        _ => return line,
+    };
+    let main = longest_move(defenses);
+    let mut main_line = defense_line(&defenses[main]);
+    main_line[0].variations = defenses
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != main)
+        .map(|(_, defense)| defense_line(defense))
+        .collect();
+    line.extend(main_line);
+    line
+}
+
+/// The line starting with `defense`, continued with the longest attack
+fn defense_line(defense: &TinueMove) -> Vec<PtnPly> {
+    let mut line = vec![PtnPly::new(defense.mv.clone())];
+    if let Some(attacks) = &defense.next {
+        if !attacks.is_empty() {
+            line.extend(attack_line(&attacks[longest_move(attacks)]));
+        }
+    }
+    line
+}
+
+/// Index of the move with the longest **Road to Tinue**, the same one `get_longest_sequence` picks
+fn longest_move(moves: &[TinueMove]) -> usize {
+    moves
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, mv)| get_longest_sequence(mv).0)
+        .map_or(0, |(i, _)| i)
+}
+
+/// Writes `plies` with their comments and variations, one full move per line.
+///
+/// Black's moves are numbered as `12...` when they don't follow white's move of the same number,
+/// e.g. at the start of a variation.
+pub fn annotated_move_text(move_number: u32, white_to_move: bool, plies: &[PtnPly]) -> String {
+    let mut lines = vec![];
+    let mut line = vec![];
+    write_line(move_number, white_to_move, plies, &mut |token, new_move| {
+        if new_move && !line.is_empty() {
+            lines.push(line.join(" "));
+            line.clear();
+        }
+        line.push(token);
+    });
+    if !line.is_empty() {
+        lines.push(line.join(" "));
+    }
+    lines.join("\n")
+}
+
+/// Passes the tokens of `plies` to `write`, together with whether a token starts a full move of this line
+fn write_line(
+    mut move_number: u32,
+    mut white_to_move: bool,
+    plies: &[PtnPly],
+    write: &mut dyn FnMut(String, bool),
+) {
+    let mut numbered = false;
+    for ply in plies {
+        if white_to_move {
+            write(format!("{}.", move_number), true);
+        } else if !numbered {
+            write(format!("{}...", move_number), false);
+        }
+        write(ply.mv.clone(), false);
+        numbered = true;
+        if let Some(comment) = &ply.comment {
+            write(format!("{{{}}}", comment), false);
+            numbered = false;
+        }
+        for variation in &ply.variations {
+            let mut tokens = vec![];
+            write_line(move_number, white_to_move, variation, &mut |token, _| {
+                tokens.push(token)
+            });
+            write(format!("({})", tokens.join(" ")), false);
+            numbered = false;
+        }
+        if !white_to_move {
+            move_number += 1;
+        }
+        white_to_move = !white_to_move;
+    }
+}
+
+/// Writes a PTN game with `tags` whose move text is `plies` from the start position
+pub fn annotated_ptn(tags: &[(&str, String)], plies: &[PtnPly]) -> String {
+    let tags = tags
+        .iter()
+        .map(|(name, value)| format!("[{} \"{}\"]\n", name, value))
+        .collect::<String>();
+    format!("{}\n{}\n", tags, annotated_move_text(1, true, plies))
+}
+
+/// A game read from a PTN file
+pub struct PtnGame {
+    /// Tag pairs in the order they appear, e.g. `("Size", "5")`
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<Mov>,
+}
+
+impl PtnGame {
+    /// Returns the value of the tag `name`, ignoring case
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Results as they appear at the end of the move text
+const PTN_RESULTS: [&str; 8] = ["R-0", "0-R", "F-0", "0-F", "1-0", "0-1", "1/2-1/2", "0-0"];
+
+/// Parses all games in `text`. A game starts with its tags, so a tag after
+/// move text starts the next game.
+///
+/// Comments, variations, move numbers, results and move annotations like `'` or `!` are dropped.
+pub fn parse_ptn_games(text: &str) -> Vec<PtnGame> {
+    let mut games = vec![];
+    let mut game = PtnGame {
+        tags: vec![],
+        moves: vec![],
+    };
+
+    for line in remove_comments(text).lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if !game.moves.is_empty() {
+                games.push(game);
+                game = PtnGame {
+                    tags: vec![],
+                    moves: vec![],
+                };
+            }
+            if let Some(tag) = parse_tag(line) {
+                game.tags.push(tag);
+            }
+            continue;
+        }
+
+        for token in line.split_whitespace() {
+            let is_move_number = token.ends_with('.')
+                && token
+                    .trim_end_matches('.')
+                    .chars()
+                    .all(|c| c.is_ascii_digit());
+            if is_move_number || token == "--" || PTN_RESULTS.contains(&token) {
+                continue;
+            }
+            let mv = token.trim_end_matches(|c: char| "'\"!?*".contains(c));
+            if !mv.is_empty() {
+                game.moves.push(mv.to_string());
+            }
+        }
+    }
+
+    if !game.moves.is_empty() || !game.tags.is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+/// Parses a tag pair like `[Player1 "Jan"]`
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().trim_matches('"');
+    Some((name.to_string(), value.to_string()))
+}
+
+/// Removes comments and variations, which may be nested
+fn remove_comments(text: &str) -> String {
+    let mut in_comment = false;
+    let mut variation_depth = 0;
+    text.chars()
+        .filter(|&c| match c {
+            _ if in_comment => {
+                in_comment = c != '}';
+                false
+            }
+            '{' => {
+                in_comment = true;
+                false
+            }
+            '(' => {
+                variation_depth += 1;
+                false
+            }
+            ')' if variation_depth > 0 => {
+                variation_depth -= 1;
+                false
+            }
+            _ => variation_depth == 0,
+        })
+        .collect()
+}
@@ -0,0 +1,535 @@
+use board_game_traits::{Color, GameResult, Position as PositionTrait};
+use pgn_traits::PgnPosition;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Instant;
+use tiltak::position::{Move, Position, Role, TunableBoard};
+
+use crate::limits::{SearchAborted, SearchBudget, SearchLimits};
+use crate::pns::pns_tinue_search_limited;
+use crate::symmetry::{
+    invariant_symmetries, transform_move, transform_tinue_moves, Symmetry, SymmetrySet,
+};
+use crate::transposition_table::{position_key, PositionKey, TranspositionTable};
+use crate::Mov;
+
+/// Represents a `Move` on the **Road to Tinue** and possible responses (`next`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct TinueMove {
+    pub mv: Mov,
+    /// When `mv` is played, any of these responses will stay on the **Road to Tinue**
+    pub next: Option<Vec<TinueMove>>,
+}
+
+/// Result of an iterative deepening search
+pub struct IDDFSResult<T> {
+    /// Number of plies needed to reach the result
+    pub depth: u32,
+    pub result: T,
+}
+
+/// Algorithm used to prove Tinues
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solver {
+    /// Iterative deepening depth-first search, see `iddf_tinue_search`
+    Iddfs,
+    /// Proof-number search, see `pns_tinue_search`
+    Pns,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iddfs" => Ok(Solver::Iddfs),
+            "pns" => Ok(Solver::Pns),
+            _ => Err(format!("Unknown solver '{}'", s)),
+        }
+    }
+}
+
+/// Searches for a Tinue of at most `max_depth` plies for `me` with the given `solver`
+pub fn tinue_search<const S: usize>(
+    solver: Solver,
+    position: &mut Position<S>,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+) -> Option<IDDFSResult<Vec<TinueMove>>> {
+    let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+    tinue_search_from(
+        solver,
+        position,
+        1,
+        max_depth,
+        me,
+        find_only_one_tinue,
+        &mut budget,
+    )
+    .expect("unlimited searches are never aborted")
+}
+
+/// Like `tinue_search`, but skips the depths below `min_depth`,
+/// e.g. because an earlier search proved that there is no shorter Tinue.
+///
+/// Returns an error if the search exceeds `budget`, in which case it is unknown whether there is a Tinue.
+pub fn tinue_search_from<const S: usize>(
+    solver: Solver,
+    position: &mut Position<S>,
+    min_depth: u32,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    budget: &mut SearchBudget,
+) -> Result<Option<IDDFSResult<Vec<TinueMove>>>, SearchAborted> {
+    match solver {
+        Solver::Iddfs => iddf_tinue_search_from(
+            position,
+            min_depth,
+            max_depth,
+            me,
+            find_only_one_tinue,
+            budget,
+        ),
+        // Proof-number search does not iterate over the depths, the lower bound only
+        // tells whether there is anything left to search
+        Solver::Pns if min_depth > max_depth => Ok(None),
+        Solver::Pns => {
+            pns_tinue_search_limited(position, max_depth, me, find_only_one_tinue, budget)
+        }
+    }
+}
+
+/// Returns the resulting tinue and the maximum length of it
+pub fn iddf_tinue_search<const S: usize>(
+    position: &mut Position<S>,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+) -> Option<IDDFSResult<Vec<TinueMove>>> {
+    let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+    iddf_tinue_search_from(position, 1, max_depth, me, find_only_one_tinue, &mut budget)
+        .expect("unlimited searches are never aborted")
+}
+
+/// Like `iddf_tinue_search`, but starts the iterative deepening at `min_depth` plies
+/// and stops when `budget` is used up
+pub fn iddf_tinue_search_from<const S: usize>(
+    position: &mut Position<S>,
+    min_depth: u32,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    budget: &mut SearchBudget,
+) -> Result<Option<IDDFSResult<Vec<TinueMove>>>, SearchAborted> {
+    iddf_tinue_search_with_pruning(
+        position,
+        min_depth,
+        max_depth,
+        me,
+        find_only_one_tinue,
+        true,
+        budget,
+    )
+}
+
+/// Like `iddf_tinue_search_from`, but the mirror images of moves in symmetric positions
+/// are only skipped if `prune_symmetries` is set
+pub(crate) fn iddf_tinue_search_with_pruning<const S: usize>(
+    position: &mut Position<S>,
+    min_depth: u32,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    prune_symmetries: bool,
+    budget: &mut SearchBudget,
+) -> Result<Option<IDDFSResult<Vec<TinueMove>>>, SearchAborted> {
+    // Shared by all iterations so deeper searches reuse the results of shallower ones
+    let mut ctx = SearchContext::new(budget, prune_symmetries);
+    for depth in (min_depth.max(1)..(max_depth + 1)).step_by(2) {
+        let nodes = ctx.budget.nodes();
+        let started = Instant::now();
+        let result = win_in_n(position, depth, me, find_only_one_tinue, &mut ctx)?;
+        let iteration_nodes = ctx.budget.nodes() - nodes;
+        ctx.budget
+            .stats
+            .record_iteration(depth, iteration_nodes, started.elapsed());
+        if !result.is_empty() {
+            return Ok(Some(IDDFSResult { depth, result }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns `true` if playing `mv` keeps `me` on a **Road to Tinue** of at most `max_depth` plies,
+/// counting `mv` itself.
+///
+/// Used to check whether the move actually played in a game still wins.
+pub fn is_on_road_to_tinue<const S: usize>(
+    solver: Solver,
+    position: &mut Position<S>,
+    mv: &Move,
+    max_depth: u32,
+    me: Color,
+    budget: &mut SearchBudget,
+) -> Result<bool, SearchAborted> {
+    let reverse_move = position.do_move(mv.clone());
+    let on_road = match position.game_result() {
+        Some(result) => Ok(is_win_for(result, me)),
+        None if max_depth <= 1 => Ok(false),
+        // The opponent is to move now, so these searches start at an AND node
+        None => match solver {
+            Solver::Iddfs => {
+                let mut ctx = SearchContext::new(budget, true);
+                iddf_win_in_n(position, max_depth - 1, me, true, &mut ctx)
+                    .map(|result| !result.is_empty())
+            }
+            Solver::Pns => pns_tinue_search_limited(position, max_depth - 1, me, true, budget)
+                .map(|result| result.is_some()),
+        },
+    };
+    position.reverse_move(reverse_move);
+    on_road
+}
+
+/// The replies that escape a threatened Tinue, see `find_saving_moves`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavingMoves {
+    pub moves: Vec<Mov>,
+    /// Length of the longest Tinue that follows a losing reply, not counting the reply.
+    /// `0` if every losing reply hands the attacker a road.
+    pub tinue_depth: u32,
+}
+
+/// Returns the replies of the side to move that avoid a Tinue of `attacker` within `depth` plies.
+///
+/// This is the AND node of `win_in_n` turned around: instead of stopping at the first escape,
+/// all escapes are collected. Returns `None` if there is no threat (every reply escapes),
+/// if every reply loses or if more than `max_saving_moves` replies escape.
+pub fn find_saving_moves<const S: usize>(
+    solver: Solver,
+    position: &mut Position<S>,
+    depth: u32,
+    attacker: Color,
+    max_saving_moves: usize,
+    budget: &mut SearchBudget,
+) -> Result<Option<SavingMoves>, SearchAborted> {
+    let mut saving_moves = vec![];
+    let mut losing_moves = 0;
+    let mut tinue_depth = 0;
+
+    for mv in ordered_moves(position) {
+        let reverse_move = position.do_move(mv.clone());
+        // The length of the Tinue after `mv`, `None` if `mv` escapes it
+        let tinue = match position.game_result() {
+            Some(result) if is_win_for(result, attacker) => Ok(Some(0)),
+            Some(_) => Ok(None),
+            None => tinue_search_from(solver, position, 1, depth, attacker, true, budget)
+                .map(|result| result.map(|result| result.depth)),
+        };
+        position.reverse_move(reverse_move);
+
+        match tinue? {
+            Some(depth) => {
+                losing_moves += 1;
+                tinue_depth = tinue_depth.max(depth);
+            }
+            None => {
+                saving_moves.push(position.move_to_san(&mv));
+                if saving_moves.len() > max_saving_moves {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    if saving_moves.is_empty() || losing_moves == 0 {
+        return Ok(None);
+    }
+    Ok(Some(SavingMoves {
+        moves: saving_moves,
+        tinue_depth,
+    }))
+}
+
+/// State shared by the recursive calls of a single IDDFS search
+struct SearchContext<'a> {
+    tt: TranspositionTable,
+    budget: &'a mut SearchBudget,
+    /// Skips the moves that are mirror images of other moves in symmetric positions
+    prune_symmetries: bool,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new(budget: &'a mut SearchBudget, prune_symmetries: bool) -> Self {
+        SearchContext {
+            tt: TranspositionTable::new(),
+            budget,
+            prune_symmetries,
+        }
+    }
+}
+
+pub(crate) fn is_win_for(result: GameResult, me: Color) -> bool {
+    result == GameResult::WhiteWin && me == Color::White
+        || result == GameResult::BlackWin && me == Color::Black
+}
+
+/// Returns all legal moves at `position`, the most promising ones first
+pub(crate) fn ordered_moves<const S: usize>(position: &Position<S>) -> Vec<Move> {
+    let mut legal_moves = vec![];
+    let mut moves_with_heuristic_scores = vec![];
+
+    position.generate_moves_with_probabilities(
+        &position.group_data(),
+        &mut legal_moves,
+        &mut moves_with_heuristic_scores,
+    );
+
+    // Sort the moves using Tiltak's heuristic
+    // Checking the best moves first gives a ~35% speedup for depth 5
+    moves_with_heuristic_scores
+        .sort_unstable_by(|(_, score1), (_, score2)| score1.partial_cmp(score2).unwrap().reverse());
+
+    moves_with_heuristic_scores
+        .into_iter()
+        .map(|(mv, _score)| mv)
+        .collect()
+}
+
+/// Returns `true` for wall placements in my last and second-last move of the Tinue.
+///
+/// Reason on last move: If placing a wall wins the game, placing a flat does so as well.
+/// Reason on second last move: If I can win after placing a wall, then I can win now as well.
+///    NB: Except that is only true for road wins: Placing a wall may stop an opponent threat and placing afterwards
+///    would then yield a win by flats or board fill.
+///    I have discussed this with Morten and the likelyhood for board-fills like that is so small it's not worth correctly
+///    implementing or sacrificing performance over at the moment, especially since only R-0 and 0-R games are analyzed.
+///    The following would improve the correctness slightly. Maybe capstone count should be considered as well.
+///      && ((me == Color::White && position.white_reserves_left() > 2)
+///      || (me == Color::Black && position.black_reserves_left() > 2))
+pub(crate) fn is_skipped_wall(mv: &Move, my_turn: bool, depth: u32) -> bool {
+    my_turn && depth <= 3 && matches!(mv, Move::Place(Role::Wall, _))
+}
+
+/// Recursive with `win_in_n` to explore each branch breadth first via IDDFS,
+/// thus removing Tinues drawn in the length by both players making moves
+/// that don't affect the Tinue.
+fn iddf_win_in_n<const S: usize>(
+    position: &mut Position<S>,
+    max_depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    ctx: &mut SearchContext,
+) -> Result<Vec<TinueMove>, SearchAborted> {
+    // Depths that are already known not to lead to a Tinue don't need to be searched again
+    let min_depth = match ctx.tt.get(position_key(position)) {
+        Some(entry) => entry.no_win_depth.map_or(1, |d| d + 1),
+        None => 1,
+    };
+    for depth in min_depth..(max_depth + 1) {
+        let result = win_in_n(position, depth, me, find_only_one_tinue, ctx)?;
+        if !result.is_empty() {
+            return Ok(result);
+        }
+    }
+    Ok(vec![])
+}
+
+/// Returns all **Roads to Tinue** for player `me`
+/// that are available at `position`.
+///
+/// `depth`: Number of plies to look into the future
+///
+/// `find_only_one_tinue`: If `true` returns only the first **Road to Tinue**
+///
+/// `ctx`: Results of earlier calls, keyed by position, and the budget of the search.
+/// Proven non-wins are returned immediately, the first move of a proven win is searched first.
+/// Results of aborted searches are not stored.
+///
+/// #### Remarks
+/// If `depth` is high, this may still return sub-optimal Tinues
+/// where the opponent is not blocking the Tinue and thus `me` can waste another ply.
+///
+/// #### Caution
+/// This method becomes very slow very quickly. `depth=5`, maybe `7` is recommended.
+/// Best to set `find_only_one_tinue=true`.
+fn win_in_n<const S: usize>(
+    position: &mut Position<S>,
+    depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    ctx: &mut SearchContext,
+) -> Result<Vec<TinueMove>, SearchAborted> {
+    ctx.budget.visit()?;
+    let key = position_key(position);
+    let proven_no_win = ctx.tt.is_proven_no_win(key, depth);
+    ctx.budget.stats.record_tt_lookup(proven_no_win);
+    if proven_no_win {
+        return Ok(vec![]);
+    }
+
+    let tinue_moves = win_in_n_uncached(position, key, depth, me, find_only_one_tinue, ctx)?;
+    match tinue_moves.first() {
+        None => ctx.tt.store_no_win(key, depth),
+        Some(first) => {
+            let best_move = match position.side_to_move() == me {
+                true => position.move_from_san(&first.mv).ok(),
+                false => None,
+            };
+            ctx.tt.store_win(key, depth, best_move);
+        }
+    }
+    Ok(tinue_moves)
+}
+
+fn win_in_n_uncached<const S: usize>(
+    position: &mut Position<S>,
+    key: PositionKey,
+    depth: u32,
+    me: Color,
+    find_only_one_tinue: bool,
+    ctx: &mut SearchContext,
+) -> Result<Vec<TinueMove>, SearchAborted> {
+    let mut tinue_moves = vec![];
+
+    let my_turn = position.side_to_move() == me;
+    ctx.budget.stats.record_node(my_turn);
+
+    // Moves that are mirror images of an earlier move in a symmetric position are not searched,
+    // they are added with the mirrored result of that move after the search
+    let symmetries = if depth > 1 && ctx.prune_symmetries {
+        ctx.tt.symmetries(key, || invariant_symmetries(&*position))
+    } else {
+        SymmetrySet::default()
+    };
+    let mut mirrored_moves: HashMap<Mov, (Mov, Symmetry)> = HashMap::new();
+    let mut pruned_moves: Vec<(Mov, Symmetry)> = vec![];
+
+    let mut moves = ordered_moves(position);
+    // When only one Tinue is needed, the move that won before is likely to win again.
+    // All moves are searched otherwise, so their order is kept.
+    if my_turn && find_only_one_tinue {
+        let best_move = ctx.tt.get(key).and_then(|entry| entry.best_move.as_ref());
+        if let Some(i) = best_move.and_then(|best| moves.iter().position(|mv| mv == best)) {
+            let best = moves.remove(i);
+            moves.insert(0, best);
+        }
+    }
+
+    for mv in moves {
+        if is_skipped_wall(&mv, my_turn, depth) {
+            continue;
+        }
+        if !symmetries.is_empty() {
+            let san = position.move_to_san(&mv);
+            if let Some((representative, symmetry)) = mirrored_moves.get(&san) {
+                pruned_moves.push((representative.clone(), *symmetry));
+                continue;
+            }
+            for symmetry in symmetries.iter() {
+                let image = transform_move(&san, symmetry, S);
+                if image != san && !mirrored_moves.contains_key(&image) {
+                    mirrored_moves.insert(image, (san.clone(), symmetry));
+                }
+            }
+        }
+
+        ctx.budget.stats.record_children(my_turn, 1);
+        let reverse_move = position.do_move(mv.clone());
+        if let Some(result) = position.game_result() {
+            // Early win or loss
+            if my_turn {
+                if result == GameResult::WhiteWin && me == Color::White
+                    || result == GameResult::BlackWin && me == Color::Black
+                {
+                    if find_only_one_tinue {
+                        position.reverse_move(reverse_move);
+                        return Ok(vec![TinueMove {
+                            mv: position.move_to_san(&mv),
+                            next: None,
+                        }]);
+                    }
+                    tinue_moves.push(TinueMove {
+                        mv: position.move_to_san(&mv),
+                        next: None,
+                    })
+                }
+            } else {
+                position.reverse_move(reverse_move);
+
+                if result == GameResult::WhiteWin && me == Color::White
+                    || result == GameResult::BlackWin && me == Color::Black
+                {
+                    // Win for me, but given to me by the opponent
+                    continue;
+                }
+
+                // Early loss or draw
+                // TODO: Actually, this could be an early road/flatwin if that's the only possible enemy move
+                //       So we should add checks for that
+                return Ok(vec![]);
+            }
+        } else if depth > 1 {
+            let winning_moves =
+                match iddf_win_in_n(position, depth - 1, me, find_only_one_tinue, ctx) {
+                    Ok(winning_moves) => winning_moves,
+                    Err(aborted) => {
+                        position.reverse_move(reverse_move);
+                        return Err(aborted);
+                    }
+                };
+            if my_turn {
+                // I play
+                if !winning_moves.is_empty() {
+                    // This move leads to Tinue
+                    let this_move = TinueMove {
+                        mv: position.move_to_san(&mv),
+                        next: Some(winning_moves),
+                    };
+
+                    if find_only_one_tinue {
+                        position.reverse_move(reverse_move);
+                        return Ok(vec![this_move]);
+                    }
+                    tinue_moves.push(this_move)
+                }
+            } else {
+                // opponent plays
+                if winning_moves.is_empty() {
+                    // Because the opponet play `mv` doesn't lead to Tinue,
+                    // this entire branch is not on the road to Tinue.
+                    position.reverse_move(reverse_move);
+                    return Ok(vec![]);
+                }
+                // This and the previous opponent moves are on the road to Tinue so add it
+                let this_move = TinueMove {
+                    mv: position.move_to_san(&mv),
+                    next: Some(winning_moves),
+                };
+                tinue_moves.push(this_move)
+            }
+        }
+        position.reverse_move(reverse_move);
+    }
+
+    // A pruned move is on the Road to Tinue exactly when its representative is,
+    // its image is inserted right after the representative
+    if !pruned_moves.is_empty() {
+        for tm in std::mem::take(&mut tinue_moves) {
+            let images: Vec<TinueMove> = pruned_moves
+                .iter()
+                .filter(|(representative, _)| *representative == tm.mv)
+                .flat_map(|(_, symmetry)| {
+                    transform_tinue_moves(std::slice::from_ref(&tm), *symmetry, S)
+                })
+                .collect();
+            tinue_moves.push(tm);
+            tinue_moves.extend(images);
+        }
+    }
+
+    Ok(tinue_moves)
+}
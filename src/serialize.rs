@@ -0,0 +1,221 @@
+use board_game_traits::Color;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{IDDFSResult, TinueFinderError, TinueMove};
+
+/// A move in PTN notation
+pub type Mov = String;
+
+/// A structure that summarises multiple `TinueMove`s into a single `struct`.
+///
+/// If the winning replies (called `solutions`) to a set of `moves` are the same
+/// then they are grouped into a single `TinueMoveOptions` instance.
+/// # TLDR
+/// Basically: If **Player A** plays one of `moves`, then **Player B** must play
+///            one of `solutions` to stay on the **Road to Tinue**.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct TinueMoveOptions {
+    /// Possible moves
+    pub moves: Vec<Mov>,
+    /// Responses applicable to any of `moves` that stay on the **Road to Tinue**.
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub solutions: Vec<TinueMoveOptions>,
+}
+
+/// Version of the JSON format of `VersionedTinue`, increased whenever a reader has to handle it differently
+pub const TINUE_FORMAT_VERSION: u32 = 1;
+
+/// A Tinue as it is stored in the database and written by the commands
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Eq, PartialEq, Clone)]
+pub struct VersionedTinue {
+    /// `TINUE_FORMAT_VERSION` of the program that wrote it
+    pub format_version: u32,
+    #[serde(flatten)]
+    pub tinue: TinueSolution,
+}
+
+/// The moves of a Tinue, tagged with `kind`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Eq, PartialEq, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TinueSolution {
+    /// Only the longest **Road to Tinue**, alternating between both players
+    Line { moves: Vec<Mov> },
+    /// Every **Road to Tinue**, see `TinueMoveOptions`
+    Options { options: Vec<TinueMoveOptions> },
+}
+
+impl VersionedTinue {
+    pub fn new(tinue: TinueSolution) -> Self {
+        VersionedTinue {
+            format_version: TINUE_FORMAT_VERSION,
+            tinue,
+        }
+    }
+}
+
+/// Reads a Tinue written by `tinue_to_json`, `None` if there was no Tinue.
+///
+/// Tinues stored before the format was versioned are a plain list of moves or of `TinueMoveOptions`,
+/// they are read as version 1. Newer versions than `TINUE_FORMAT_VERSION` are rejected.
+pub fn parse_tinue_json(json: &str) -> Result<Option<VersionedTinue>, TinueFinderError> {
+    let value: Value = serde_json::from_str(json)?;
+    let tinue = match value {
+        Value::Null => return Ok(None),
+        Value::Array(items) if items.iter().all(Value::is_string) => {
+            VersionedTinue::new(TinueSolution::Line {
+                moves: serde_json::from_value(Value::Array(items))?,
+            })
+        }
+        Value::Array(items) => VersionedTinue::new(TinueSolution::Options {
+            options: serde_json::from_value(Value::Array(items))?,
+        }),
+        value => serde_json::from_value::<VersionedTinue>(value)?,
+    };
+    if tinue.format_version > TINUE_FORMAT_VERSION {
+        return Err(TinueFinderError::UnsupportedFormatVersion(
+            tinue.format_version,
+        ));
+    }
+    Ok(Some(tinue))
+}
+
+/// JSON schema of the Tinues written by `tinue_to_json`, which may also be `null`
+pub fn tinue_json_schema() -> Result<Value, TinueFinderError> {
+    Ok(serde_json::to_value(schema_for!(Option<VersionedTinue>))?)
+}
+
+/// Reduces `TinueMove`s to `TinueMoveOption`s
+pub fn tinuemove_to_options(tmvs: &[TinueMove]) -> Vec<TinueMoveOptions> {
+    let trs: Vec<(Mov, Option<Vec<TinueMoveOptions>>)> = tmvs
+        .iter()
+        .map(|tm| {
+            (
+                tm.mv.clone(),
+                tm.next.as_ref().map(|next| tinuemove_to_options(&next)),
+            )
+        })
+        .collect();
+
+    let groups = trs.group_by(|(_, next1), (_, next2)| next1 == next2);
+
+    groups
+        .map(|group| TinueMoveOptions {
+            moves: group
+                .iter()
+                .map(|(mv, _)| mv.clone())
+                .collect::<Vec<String>>(),
+            solutions: match group.first() {
+                None => vec![],
+                Some((_, solution)) => solution.clone().unwrap_or_default(),
+            },
+        })
+        .collect()
+}
+
+/// Expands `TinueMoveOption`s back to a `TinueMove` for each of their moves, the inverse of `tinuemove_to_options`
+pub fn options_to_tinuemoves(options: &[TinueMoveOptions]) -> Vec<TinueMove> {
+    options
+        .iter()
+        .flat_map(|option| {
+            option.moves.iter().map(move |mv| TinueMove {
+                mv: mv.clone(),
+                next: match option.solutions.is_empty() {
+                    true => None,
+                    false => Some(options_to_tinuemoves(&option.solutions)),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Concatenates the List into a vector
+pub fn move_list_to_vec(mv: MoveListNode) -> Vec<Mov> {
+    if let Some(next) = mv.next {
+        let mut list = move_list_to_vec(*next);
+        list.insert(0, mv.mv);
+        return list;
+    }
+    return vec![mv.mv];
+}
+
+/// A single line of a **Road to Tinue** as a linked list
+pub struct MoveListNode {
+    pub mv: Mov,
+    pub next: Option<Box<MoveListNode>>,
+}
+
+/// Returns a longest **Road to Tinue**.
+///
+/// This is probably a road where the opponent defends *fairly* well (that's hard to measure because it's a Tinue and no move is an effective defense)
+pub fn get_longest_sequence(tinue_move: &TinueMove) -> (usize, MoveListNode) {
+    if let Some(next) = &tinue_move.next {
+        let longest_sequence = next
+            .iter()
+            .map(|n| get_longest_sequence(n))
+            .max_by_key(|(depth, _)| *depth);
+
+        if let Some((depth, next_move)) = longest_sequence {
+            return (
+                depth + 1,
+                MoveListNode {
+                    mv: tinue_move.mv.clone(),
+                    next: Some(Box::new(next_move)),
+                },
+            );
+        }
+    }
+
+    (
+        1,
+        MoveListNode {
+            mv: tinue_move.mv.clone(),
+            next: None,
+        },
+    )
+}
+
+/// Serializes the result of a search to the JSON stored in the database, see `VersionedTinue`.
+///
+/// `moves` include a first move from `me` and then answers to all possible replies from `opponent`
+/// To reduce the data saved to the database (this one would be massive) the decision was taken to store only
+/// a single example of a Tinue (the longest one available) as a `TinueSolution::Line` if find_only_one_tinue
+pub fn tinue_to_json(
+    moves: Option<&IDDFSResult<Vec<TinueMove>>>,
+    find_only_one_tinue: bool,
+) -> Result<String, TinueFinderError> {
+    Ok(serde_json::to_string(&tinue_to_value(
+        moves,
+        find_only_one_tinue,
+    )?)?)
+}
+
+/// Like `tinue_to_json`, but returns the JSON value to embed it in other records
+pub fn tinue_to_value(
+    moves: Option<&IDDFSResult<Vec<TinueMove>>>,
+    find_only_one_tinue: bool,
+) -> Result<Value, TinueFinderError> {
+    let tinue = moves.map(|IDDFSResult { depth: _, result }| {
+        VersionedTinue::new(match find_only_one_tinue {
+            true => TinueSolution::Line {
+                moves: result
+                    .first()
+                    .map(|m| move_list_to_vec(get_longest_sequence(m).1))
+                    .unwrap_or_default(),
+            },
+            false => TinueSolution::Options {
+                options: tinuemove_to_options(result),
+            },
+        })
+    });
+    Ok(serde_json::to_value(&tinue)?)
+}
+
+/// Name of `color` as stored in the database
+pub fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Serialize, Serializer};
+use std::time::Duration;
+
+/// Nodes and time of all iterative deepening iterations of one depth
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IterationStats {
+    pub depth: u32,
+    /// Number of searches that ran an iteration of this depth
+    pub searches: u32,
+    pub nodes: u64,
+    #[serde(rename = "timeMs", serialize_with = "serialize_millis")]
+    pub time: Duration,
+}
+
+/// Counters collected by the searches of a single game, used to tune the solvers.
+///
+/// Serialized with the raw counters, the values derived from them are left to the reader.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchStats {
+    /// Number of visited positions
+    pub nodes: u64,
+    #[serde(rename = "timeMs", serialize_with = "serialize_millis")]
+    pub time: Duration,
+    /// Positions in which the attacker is to move (one winning move suffices)
+    pub or_nodes: u64,
+    /// Moves searched at OR nodes
+    pub or_children: u64,
+    /// Positions in which the defender is to move (every reply must lose)
+    pub and_nodes: u64,
+    /// Moves searched at AND nodes
+    pub and_children: u64,
+    /// Lookups in the transposition table, `0` for solvers without one
+    pub tt_lookups: u64,
+    /// Lookups that made a search unnecessary
+    pub tt_hits: u64,
+    /// Sorted by depth
+    pub iterations: Vec<IterationStats>,
+}
+
+impl SearchStats {
+    /// Counts a position whose moves are searched
+    pub fn record_node(&mut self, my_turn: bool) {
+        if my_turn {
+            self.or_nodes += 1;
+        } else {
+            self.and_nodes += 1;
+        }
+    }
+
+    /// Counts moves searched at an OR node (`my_turn`) or at an AND node
+    pub fn record_children(&mut self, my_turn: bool, count: u64) {
+        if my_turn {
+            self.or_children += count;
+        } else {
+            self.and_children += count;
+        }
+    }
+
+    pub fn record_tt_lookup(&mut self, hit: bool) {
+        self.tt_lookups += 1;
+        if hit {
+            self.tt_hits += 1;
+        }
+    }
+
+    /// Adds an iteration of the iterative deepening of `depth` plies
+    pub fn record_iteration(&mut self, depth: u32, nodes: u64, time: Duration) {
+        let index = match self.iterations.binary_search_by_key(&depth, |i| i.depth) {
+            Ok(index) => index,
+            Err(index) => {
+                self.iterations.insert(
+                    index,
+                    IterationStats {
+                        depth,
+                        ..IterationStats::default()
+                    },
+                );
+                index
+            }
+        };
+        let iteration = &mut self.iterations[index];
+        iteration.searches += 1;
+        iteration.nodes += nodes;
+        iteration.time += time;
+    }
+
+    pub fn nodes_per_second(&self) -> f64 {
+        match self.time.as_secs_f64() {
+            secs if secs > 0.0 => self.nodes as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    /// Average number of moves searched per OR node
+    pub fn or_branching_factor(&self) -> f64 {
+        ratio(self.or_children, self.or_nodes)
+    }
+
+    /// Average number of moves searched per AND node
+    pub fn and_branching_factor(&self) -> f64 {
+        ratio(self.and_children, self.and_nodes)
+    }
+
+    /// Share of the lookups that were hits, `None` if there is no transposition table
+    pub fn tt_hit_rate(&self) -> Option<f64> {
+        match self.tt_lookups {
+            0 => None,
+            lookups => Some(ratio(self.tt_hits, lookups)),
+        }
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    match denominator {
+        0 => 0.0,
+        _ => numerator as f64 / denominator as f64,
+    }
+}
+
+fn serialize_millis<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(time.as_millis() as u64)
+}
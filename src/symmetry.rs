@@ -0,0 +1,332 @@
+use std::iter::FromIterator;
+use tiltak::position::{Position, Square};
+
+use crate::{Mov, TinueFinderError, TinueMove};
+
+/// One of the 8 symmetries of the square board: a reflection followed by a rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symmetry {
+    /// Mirrors the files before rotating
+    pub mirrored: bool,
+    /// Number of quarter turns
+    pub rotations: u8,
+}
+
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry {
+        mirrored: false,
+        rotations: 0,
+    };
+
+    /// All symmetries, starting with the identity
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        (0..8).map(|i| Symmetry {
+            mirrored: i >= 4,
+            rotations: i % 4,
+        })
+    }
+
+    /// Maps the square at `file` and `rank` (both starting at `0`) on a board of `size`
+    pub fn map_square(self, file: usize, rank: usize, size: usize) -> (usize, usize) {
+        let (mut file, mut rank) = if self.mirrored {
+            (size - 1 - file, rank)
+        } else {
+            (file, rank)
+        };
+        for _ in 0..self.rotations {
+            let rotated = (rank, size - 1 - file);
+            file = rotated.0;
+            rank = rotated.1;
+        }
+        (file, rank)
+    }
+
+    /// Bit of the symmetry in a `SymmetrySet`, in the order of `all`
+    fn bit(self) -> u8 {
+        1 << (self.rotations + if self.mirrored { 4 } else { 0 })
+    }
+}
+
+/// A set of symmetries, small enough to be cached for every position
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SymmetrySet(u8);
+
+impl SymmetrySet {
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, symmetry: Symmetry) -> bool {
+        self.0 & symmetry.bit() != 0
+    }
+
+    /// The symmetries of the set, in the order of `Symmetry::all`
+    pub fn iter(self) -> impl Iterator<Item = Symmetry> {
+        Symmetry::all().filter(move |&symmetry| self.contains(symmetry))
+    }
+}
+
+impl FromIterator<Symmetry> for SymmetrySet {
+    fn from_iter<I: IntoIterator<Item = Symmetry>>(symmetries: I) -> Self {
+        SymmetrySet(
+            symmetries
+                .into_iter()
+                .fold(0, |bits, symmetry| bits | symmetry.bit()),
+        )
+    }
+}
+
+/// The squares of a TPS string, `squares[rank][file]` with rank `0` being the first rank.
+///
+/// Empty squares are empty strings, stacks are written as in TPS, e.g. `12S`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TpsBoard {
+    pub size: usize,
+    pub squares: Vec<Vec<String>>,
+    /// `1` if white is to move, `2` if black is to move
+    pub side_to_move: char,
+}
+
+impl TpsBoard {
+    /// Parses the board and the player to move of `tps`, the move number is ignored
+    pub fn parse(tps: &str) -> Result<Self, TinueFinderError> {
+        let invalid = |reason: &str| TinueFinderError::InvalidTps {
+            tps: tps.to_string(),
+            reason: reason.to_string(),
+        };
+        let mut fields = tps.split_whitespace();
+        let board = fields.next().ok_or_else(|| invalid("empty"))?;
+        let side_to_move = match fields.next() {
+            Some("1") => '1',
+            Some("2") => '2',
+            _ => return Err(invalid("the player to move must be 1 or 2")),
+        };
+
+        let rows: Vec<&str> = board.split('/').collect();
+        let size = rows.len();
+        let mut squares = vec![];
+        // The first row of the TPS is the last rank
+        for row in rows.iter().rev() {
+            let mut rank = vec![];
+            for square in row.split(',') {
+                match square.strip_prefix('x') {
+                    Some("") => rank.push(String::new()),
+                    Some(count) => {
+                        let count = count
+                            .parse::<usize>()
+                            .map_err(|_| invalid("invalid number of empty squares"))?;
+                        rank.extend(std::iter::repeat(String::new()).take(count));
+                    }
+                    None => rank.push(square.to_string()),
+                }
+            }
+            if rank.len() != size {
+                return Err(invalid("the board is not square"));
+            }
+            squares.push(rank);
+        }
+
+        Ok(TpsBoard {
+            size,
+            squares,
+            side_to_move,
+        })
+    }
+
+    /// Applies `symmetry` and, if `swap_colors` is set, exchanges the pieces and the turn of the players
+    pub fn transform(&self, symmetry: Symmetry, swap_colors: bool) -> Self {
+        let mut squares = vec![vec![String::new(); self.size]; self.size];
+        for (rank, row) in self.squares.iter().enumerate() {
+            for (file, stack) in row.iter().enumerate() {
+                let (new_file, new_rank) = symmetry.map_square(file, rank, self.size);
+                squares[new_rank][new_file] = if swap_colors {
+                    stack.chars().map(swap_color).collect()
+                } else {
+                    stack.clone()
+                };
+            }
+        }
+        TpsBoard {
+            size: self.size,
+            squares,
+            side_to_move: if swap_colors {
+                swap_color(self.side_to_move)
+            } else {
+                self.side_to_move
+            },
+        }
+    }
+
+    /// Returns `true` if `symmetry` maps the board onto itself.
+    ///
+    /// Unlike comparing with `transform`, nothing is copied and it stops at the first square that differs.
+    pub fn is_invariant(&self, symmetry: Symmetry) -> bool {
+        self.squares.iter().enumerate().all(|(rank, row)| {
+            row.iter().enumerate().all(|(file, stack)| {
+                let (new_file, new_rank) = symmetry.map_square(file, rank, self.size);
+                self.squares[new_rank][new_file] == *stack
+            })
+        })
+    }
+
+    /// Writes the board and the player to move as TPS, without the move number
+    pub fn to_tps_key(&self) -> String {
+        let rows: Vec<String> = self
+            .squares
+            .iter()
+            .rev()
+            .map(|rank| {
+                let mut squares: Vec<String> = vec![];
+                let mut empty = 0;
+                for stack in rank {
+                    if stack.is_empty() {
+                        empty += 1;
+                        continue;
+                    }
+                    if empty > 0 {
+                        squares.push(empty_squares(empty));
+                        empty = 0;
+                    }
+                    squares.push(stack.clone());
+                }
+                if empty > 0 {
+                    squares.push(empty_squares(empty));
+                }
+                squares.join(",")
+            })
+            .collect();
+        format!("{} {}", rows.join("/"), self.side_to_move)
+    }
+}
+
+fn empty_squares(count: usize) -> String {
+    match count {
+        1 => "x".to_string(),
+        _ => format!("x{}", count),
+    }
+}
+
+fn swap_color(c: char) -> char {
+    match c {
+        '1' => '2',
+        '2' => '1',
+        c => c,
+    }
+}
+
+/// Returns a key that is the same for all positions that are equal up to a rotation or reflection
+/// of the board and swapping the colours of the players.
+///
+/// The key is the lexicographically smallest of the transformed positions,
+/// written as TPS without the move number.
+pub fn canonical_key(tps: &str) -> Result<String, TinueFinderError> {
+    let board = TpsBoard::parse(tps)?;
+    let mut keys = vec![];
+    for symmetry in Symmetry::all() {
+        keys.push(board.transform(symmetry, false).to_tps_key());
+        keys.push(board.transform(symmetry, true).to_tps_key());
+    }
+    Ok(keys.into_iter().min().unwrap())
+}
+
+/// Returns the symmetries other than the identity that map `position` onto itself.
+///
+/// Like `TpsBoard::is_invariant`, the stacks are compared in place on the board of the position.
+pub fn invariant_symmetries<const S: usize>(position: &Position<S>) -> SymmetrySet {
+    Symmetry::all()
+        .skip(1)
+        .filter(|&symmetry| {
+            (0..S).all(|rank| {
+                (0..S).all(|file| {
+                    let (new_file, new_rank) = symmetry.map_square(file, rank, S);
+                    position[square::<S>(file, rank)] == position[square::<S>(new_file, new_rank)]
+                })
+            })
+        })
+        .collect()
+}
+
+/// The square at `file` and `rank` as in `Symmetry::map_square`, tiltak counts the ranks from the top of the board
+fn square<const S: usize>(file: usize, rank: usize) -> Square {
+    Square(((S - 1 - rank) * S + file) as u8)
+}
+
+/// Applies `symmetry` to a move in PTN on a board of `size`, e.g. `3a1+12` or `Cb2`.
+///
+/// Returns the move unchanged if it cannot be parsed.
+pub fn transform_move(mv: &str, symmetry: Symmetry, size: usize) -> Mov {
+    let chars: Vec<char> = mv.chars().collect();
+    let mut i = 0;
+    while i < chars.len() && chars[i].is_ascii_digit() {
+        i += 1;
+    }
+    if i < chars.len() && "CSF".contains(chars[i]) {
+        i += 1;
+    }
+    if i + 1 >= chars.len() || !chars[i].is_ascii_lowercase() || !chars[i + 1].is_ascii_digit() {
+        return mv.to_string();
+    }
+    let file = (chars[i] as u8 - b'a') as usize;
+    let rank = (chars[i + 1] as u8 - b'1') as usize;
+    if file >= size || rank >= size {
+        return mv.to_string();
+    }
+    let (new_file, new_rank) = symmetry.map_square(file, rank, size);
+
+    let mut transformed: String = chars[..i].iter().collect();
+    transformed.push((b'a' + new_file as u8) as char);
+    transformed.push((b'1' + new_rank as u8) as char);
+    let mut rest = chars[i + 2..].iter();
+    if let Some(&direction) = chars.get(i + 2) {
+        if let Some(direction) = transform_direction(direction, symmetry) {
+            transformed.push(direction);
+            rest.next();
+        }
+    }
+    transformed.extend(rest);
+    transformed
+}
+
+/// Applies `symmetry` to the direction of a stack movement, `None` if `direction` is none
+fn transform_direction(direction: char, symmetry: Symmetry) -> Option<char> {
+    // (file, rank) steps, transformed like squares relative to the centre
+    let (mut file, mut rank): (i8, i8) = match direction {
+        '+' => (0, 1),
+        '-' => (0, -1),
+        '>' => (1, 0),
+        '<' => (-1, 0),
+        _ => return None,
+    };
+    if symmetry.mirrored {
+        file = -file;
+    }
+    for _ in 0..symmetry.rotations {
+        let rotated = (rank, -file);
+        file = rotated.0;
+        rank = rotated.1;
+    }
+    Some(match (file, rank) {
+        (0, 1) => '+',
+        (0, -1) => '-',
+        (1, 0) => '>',
+        _ => '<',
+    })
+}
+
+/// Applies `symmetry` to every move of a **Road to Tinue**
+pub fn transform_tinue_moves(
+    tinue_moves: &[TinueMove],
+    symmetry: Symmetry,
+    size: usize,
+) -> Vec<TinueMove> {
+    tinue_moves
+        .iter()
+        .map(|tm| TinueMove {
+            mv: transform_move(&tm.mv, symmetry, size),
+            next: tm
+                .next
+                .as_ref()
+                .map(|next| transform_tinue_moves(next, symmetry, size)),
+        })
+        .collect()
+}
@@ -0,0 +1,51 @@
+use crate::compact::{decode_tinue_tree, encode_tinue_tree};
+use crate::tests::tinue_move;
+use crate::{TinueFinderError, TinueMove};
+
+/// Every defense is answered by the same winning move, so the subtrees are shared
+fn tree() -> Vec<TinueMove> {
+    let defenses = ["b1", "b2", "b3", "b4", "b5"];
+    let attack = |mv: &str| {
+        tinue_move(
+            mv,
+            Some(
+                defenses
+                    .iter()
+                    .map(|defense| tinue_move(defense, Some(vec![tinue_move("e1", None)])))
+                    .collect(),
+            ),
+        )
+    };
+    vec![attack("a1"), attack("a2"), tinue_move("a3", Some(vec![]))]
+}
+
+#[test]
+fn encode_tinue_tree_round_trip_test() {
+    let tree = tree();
+    let encoded = encode_tinue_tree(&tree).unwrap();
+    assert_eq!(decode_tinue_tree(&encoded).unwrap(), tree);
+
+    assert_eq!(
+        decode_tinue_tree(&encode_tinue_tree(&[]).unwrap()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn decode_invalid_tinue_tree_test() {
+    assert!(matches!(
+        decode_tinue_tree(b"[\"a1\"]"),
+        Err(TinueFinderError::InvalidTinueTree(_))
+    ));
+
+    let mut encoded = encode_tinue_tree(&tree()).unwrap();
+    encoded[3] = 99;
+    assert!(matches!(
+        decode_tinue_tree(&encoded),
+        Err(TinueFinderError::UnsupportedFormatVersion(99))
+    ));
+
+    let mut encoded = encode_tinue_tree(&tree()).unwrap();
+    encoded.truncate(encoded.len() / 2);
+    assert!(decode_tinue_tree(&encoded).is_err());
+}
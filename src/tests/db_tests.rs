@@ -0,0 +1,289 @@
+use crate::db::{self, ScanOutcome, ScanRunRow, ScannedGameRow, TinueGameRow};
+use crate::game_source::PLAYTAK_SOURCE;
+use crate::symmetry::canonical_key;
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+
+fn scan_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::create_scan_tables(&conn).unwrap();
+    conn
+}
+
+/// A run of plain tinue searches of 5s games from playtak
+fn scan_run(max_depth: u32) -> ScanRunRow {
+    ScanRunRow {
+        source: PLAYTAK_SOURCE.to_string(),
+        size: 5,
+        mode: "tinues",
+        plies_to_undo: 3,
+        max_depth,
+        multi_tinue: false,
+        solver: "Iddfs".to_string(),
+        max_nodes: None,
+        max_time_secs: None,
+    }
+}
+
+fn insert_scanned_games(conn: &Connection, run: &ScanRunRow, games: &[(u32, ScanOutcome)]) {
+    let run_id = db::insert_scan_run(conn, run).unwrap();
+    for (gameid, outcome) in games.iter() {
+        db::insert_scanned_game(
+            conn,
+            &ScannedGameRow {
+                run_id,
+                gameid: *gameid,
+                outcome: *outcome,
+                tinue_depth: None,
+                error: None,
+                stats: None,
+            },
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn select_scanned_game_ids_test() {
+    let conn = scan_db();
+    let run = scan_run(5);
+    insert_scanned_games(
+        &conn,
+        &run,
+        &[
+            (1, ScanOutcome::NoTinue),
+            (2, ScanOutcome::Tinue),
+            (3, ScanOutcome::Error),
+            (4, ScanOutcome::Aborted),
+        ],
+    );
+    // Runs with other settings
+    insert_scanned_games(&conn, &scan_run(7), &[(10, ScanOutcome::NoTinue)]);
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            size: 6,
+            ..scan_run(5)
+        },
+        &[(11, ScanOutcome::NoTinue)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            mode: "defense",
+            ..scan_run(5)
+        },
+        &[(12, ScanOutcome::NoTinue)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            plies_to_undo: 5,
+            ..scan_run(5)
+        },
+        &[(13, ScanOutcome::NoTinue)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            multi_tinue: true,
+            ..scan_run(5)
+        },
+        &[(14, ScanOutcome::NoTinue)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            source: "ptn:/games".to_string(),
+            ..scan_run(5)
+        },
+        &[(15, ScanOutcome::NoTinue)],
+    );
+    // A later run with the same settings
+    insert_scanned_games(
+        &conn,
+        &run,
+        &[(4, ScanOutcome::Aborted), (5, ScanOutcome::NoTinue)],
+    );
+
+    let ids = db::select_scanned_game_ids(&conn, &run).unwrap();
+    assert_eq!(ids, [1, 2, 3, 5].iter().copied().collect::<HashSet<u32>>());
+}
+
+#[test]
+fn select_no_tinue_depths_test() {
+    let conn = scan_db();
+    insert_scanned_games(
+        &conn,
+        &scan_run(3),
+        &[
+            (1, ScanOutcome::NoTinue),
+            (2, ScanOutcome::NoTinue),
+            (3, ScanOutcome::ImmediateWin),
+        ],
+    );
+    insert_scanned_games(
+        &conn,
+        &scan_run(5),
+        &[(1, ScanOutcome::NoTinue), (2, ScanOutcome::Tinue)],
+    );
+    // Neither is a lower bound
+    insert_scanned_games(
+        &conn,
+        &scan_run(7),
+        &[(1, ScanOutcome::Aborted), (2, ScanOutcome::Error)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            plies_to_undo: 5,
+            ..scan_run(7)
+        },
+        &[(4, ScanOutcome::NoTinue)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            mode: "defense",
+            ..scan_run(7)
+        },
+        &[(5, ScanOutcome::NoTinue)],
+    );
+    insert_scanned_games(
+        &conn,
+        &ScanRunRow {
+            source: "ptn:/games".to_string(),
+            ..scan_run(7)
+        },
+        &[(6, ScanOutcome::NoTinue)],
+    );
+    // Runs stored before the source was recorded read playtak games
+    conn.execute(
+        "INSERT INTO scan_runs(size, mode, plies_to_undo, max_depth, multi_tinue, solver) VALUES(5, 'tinues', 3, 9, 0, 'Iddfs')",
+        rusqlite::params![],
+    )
+    .unwrap();
+    db::insert_scanned_game(
+        &conn,
+        &ScannedGameRow {
+            run_id: conn.last_insert_rowid(),
+            gameid: 7,
+            outcome: ScanOutcome::NoTinue,
+            tinue_depth: None,
+            error: None,
+            stats: None,
+        },
+    )
+    .unwrap();
+
+    let depths = db::select_no_tinue_depths(&conn, PLAYTAK_SOURCE, 5, 3).unwrap();
+    assert_eq!(
+        depths,
+        [(1, 5), (2, 3), (7, 9)]
+            .iter()
+            .copied()
+            .collect::<HashMap<u32, u32>>()
+    );
+}
+
+#[test]
+fn select_tinues_without_added_columns_test() {
+    let conn = Connection::open_in_memory().unwrap();
+    assert!(db::select_tinues(&conn, None).unwrap().is_empty());
+    assert!(db::select_tinue_stats(&conn).unwrap().is_empty());
+
+    // The table of the first version
+    conn.execute_batch(
+        "CREATE TABLE tinues (
+        id integer primary key,
+        gameid integer NOT NULL,
+        size integer,
+        plies_to_undo integer,
+        tinue_depth integer,
+        tinue TEXT);
+        INSERT INTO tinues(gameid, size, plies_to_undo, tinue_depth, tinue) VALUES(7, 5, 3, 3, '[\"a1\"]');",
+    )
+    .unwrap();
+
+    let rows = db::select_tinues(&conn, Some(5)).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].tinue.gameid, 7);
+    assert_eq!(rows[0].tinue.game_source(), PLAYTAK_SOURCE);
+    assert_eq!(rows[0].tinue.tps, None);
+    assert_eq!(rows[0].tinue.tinue_tree, None);
+    assert_eq!(rows[0].duplicate_of, None);
+
+    let stats = db::select_tinue_stats(&conn).unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(
+        (stats[0].tinues, stats[0].games, stats[0].duplicates),
+        (1, 1, 0)
+    );
+
+    // Reading does not migrate the table
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(tinues)")
+        .unwrap()
+        .query_map(rusqlite::params![], |row| row.get(1))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(columns.len(), 6);
+}
+
+/// A tinue of depth 3 found in `gameid`, in the position `tps`
+fn tinue_row(gameid: u32, tps: &str) -> TinueGameRow {
+    TinueGameRow {
+        gameid,
+        source: Some(PLAYTAK_SOURCE.to_string()),
+        size: 5,
+        plies_to_undo: 3,
+        tinue_depth: 3,
+        tinue: "[]".to_string(),
+        canonical_key: Some(canonical_key(tps).unwrap()),
+        tps: Some(tps.to_string()),
+        side_to_move: None,
+        ply: None,
+        played_move: None,
+        tinue_tree: None,
+    }
+}
+
+#[test]
+fn insert_tinue_duplicate_test() {
+    let conn = Connection::open_in_memory().unwrap();
+    db::create_tinues_table(&conn).unwrap();
+
+    db::insert_tinue(&conn, &tinue_row(1, "2,1,x3/x5/x5/x5/x5 1 5")).unwrap();
+    // Rotated by 180 degrees
+    db::insert_tinue(&conn, &tinue_row(2, "x5/x5/x5/x5/x3,1,2 1 9")).unwrap();
+    // The colours swapped
+    db::insert_tinue(&conn, &tinue_row(3, "1,2,x3/x5/x5/x5/x5 2 5")).unwrap();
+    // The other player to move
+    db::insert_tinue(&conn, &tinue_row(4, "2,1,x3/x5/x5/x5/x5 2 5")).unwrap();
+    db::insert_tinue(
+        &conn,
+        &TinueGameRow {
+            canonical_key: None,
+            ..tinue_row(5, "2,1,x3/x5/x5/x5/x5 1 5")
+        },
+    )
+    .unwrap();
+
+    let rows = db::select_tinues(&conn, Some(5)).unwrap();
+    let duplicates: Vec<(u32, Option<u32>)> = rows
+        .iter()
+        .map(|row| (row.tinue.gameid, row.duplicate_of))
+        .collect();
+    assert_eq!(
+        duplicates,
+        vec![(1, None), (2, Some(1)), (3, Some(1)), (4, None), (5, None)]
+    );
+
+    let stats = db::select_tinue_stats(&conn).unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(
+        (stats[0].tinues, stats[0].games, stats[0].duplicates),
+        (5, 5, 2)
+    );
+}
@@ -0,0 +1,44 @@
+use crate::search::{find_saving_moves, SavingMoves};
+use crate::tests::tinue_position;
+use crate::tps::position_from_tps;
+use crate::{SearchBudget, SearchLimits, Solver};
+use board_game_traits::{Color, Position as PositionTrait};
+use pgn_traits::PgnPosition;
+use tiltak::position::Position;
+
+/// White threatens roads on a1 and c1, black can only escape by completing its own road with b1
+const ONE_SAVING_MOVE_TPS: &str = "1,2,1/1,2,1/x3 2 4";
+
+#[test]
+fn one_saving_move_test() {
+    for solver in [Solver::Iddfs, Solver::Pns].iter() {
+        let mut position: Position<3> = position_from_tps(ONE_SAVING_MOVE_TPS, None).unwrap();
+        let fen = position.to_fen();
+        let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+        let saving_moves =
+            find_saving_moves(*solver, &mut position, 1, Color::White, 3, &mut budget).unwrap();
+        assert_eq!(
+            saving_moves,
+            Some(SavingMoves {
+                moves: vec!["b1".to_string()],
+                tinue_depth: 1,
+            })
+        );
+        assert_eq!(position.to_fen(), fen);
+    }
+}
+
+#[test]
+fn no_saving_move_test() {
+    // White has played into the Tinue of `tinue_tests_5s::tinue_test`
+    let mut position = tinue_position();
+    let mv = position.move_from_san("2c5>11").unwrap();
+    position.do_move(mv);
+
+    for solver in [Solver::Iddfs, Solver::Pns].iter() {
+        let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+        let saving_moves =
+            find_saving_moves(*solver, &mut position, 2, Color::White, 3, &mut budget).unwrap();
+        assert_eq!(saving_moves, None);
+    }
+}
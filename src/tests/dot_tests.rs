@@ -0,0 +1,38 @@
+use crate::dot::tinue_to_dot;
+use crate::tests::tinue_move;
+
+#[test]
+fn tinue_to_dot_test() {
+    let tinue = vec![
+        tinue_move(
+            "a1",
+            Some(vec![
+                tinue_move("b1", Some(vec![tinue_move("e1", None)])),
+                tinue_move("b2", Some(vec![tinue_move("e2", None)])),
+                tinue_move("b3", Some(vec![tinue_move("e1", None)])),
+            ]),
+        ),
+        // Same replies as the first move, grouped into one node
+        tinue_move(
+            "a2",
+            Some(vec![tinue_move("c1", Some(vec![tinue_move("e1", None)]))]),
+        ),
+        tinue_move(
+            "a3",
+            Some(vec![tinue_move("c1", Some(vec![tinue_move("e1", None)]))]),
+        ),
+    ];
+    let dot = tinue_to_dot(&tinue, "x5/x5/x5/x5/x5 1 1");
+
+    assert!(dot.starts_with("digraph tinue {"));
+    assert!(dot.contains("root [label=\"x5/x5/x5/x5/x5 1 1\", shape=plaintext];"));
+    assert!(dot.contains("n0 [label=\"a1\", shape=box"));
+    assert!(dot.contains("n1 [label=\"b1\", shape=ellipse"));
+    assert!(dot.contains("[label=\"a2\\na3\", shape=box"));
+
+    // The winning move `e1` after `b1`, `b3` and `c1` is a single node
+    assert_eq!(dot.matches("label=\"e1\"").count(), 1);
+    assert!(dot.contains("n1 -> n2;"));
+    assert!(dot.contains("n5 -> n2;"));
+    assert!(dot.contains("n7 -> n2;"));
+}
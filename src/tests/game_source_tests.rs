@@ -0,0 +1,143 @@
+use crate::game_source::{
+    GameFilter, GameSource, PtnDirectorySource, PtnTarSource, PtnZipSource, SqliteGameSource,
+};
+use crate::TinueFinderError;
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use zip::write::{FileOptions, ZipWriter};
+
+const PTN: &str = r#"[Size "5"]
+[Result "R-0"]
+
+1. a1 e5 2. a2 -- R-0
+
+[Size "5"]
+[Result "0-R"]
+
+1. a1 e5 2. e4 -- 0-R
+"#;
+
+/// The ids of the games, `None` for those that could not be read
+fn game_ids(source: &dyn GameSource) -> Vec<Option<u32>> {
+    let filter = GameFilter {
+        board_size: 5,
+        min_game_id: 0,
+    };
+    source
+        .road_wins(&filter)
+        .unwrap()
+        .map(|game| game.ok().map(|game| game.id))
+        .collect()
+}
+
+#[test]
+fn ptn_directory_source_test() {
+    let dir = std::env::temp_dir().join(format!("tinue-finder-ptn-{}", std::process::id()));
+    fs::create_dir_all(dir.join("b")).unwrap();
+    fs::write(dir.join("a.ptn"), PTN).unwrap();
+    fs::write(dir.join("b").join("c.ptn"), PTN).unwrap();
+
+    let source = PtnDirectorySource::new(&dir);
+    let ids = game_ids(&source);
+    assert_eq!(ids.len(), 4);
+    assert!(ids.iter().all(Option::is_some));
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 4);
+
+    // An unreadable file doesn't change the ids of the other games
+    fs::write(dir.join("0.ptn"), [0xff, 0xfe]).unwrap();
+    let mut ids_with_unreadable = vec![None];
+    ids_with_unreadable.extend(ids.iter().copied());
+    assert_eq!(game_ids(&source), ids_with_unreadable);
+
+    // The same files in archives get the same ids
+    let zip_path = dir.with_extension("zip");
+    let mut zip = ZipWriter::new(fs::File::create(&zip_path).unwrap());
+    for name in ["a.ptn", "b/c.ptn"].iter() {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(PTN.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+    assert_eq!(game_ids(&PtnZipSource::new(&zip_path)), ids);
+
+    let tar_path = dir.with_extension("tar");
+    let mut tar = tar::Builder::new(fs::File::create(&tar_path).unwrap());
+    for name in ["a.ptn", "b/c.ptn"].iter() {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(PTN.len() as u64);
+        header.set_mode(0o644);
+        tar.append_data(&mut header, name, PTN.as_bytes()).unwrap();
+    }
+    tar.finish().unwrap();
+    drop(tar);
+    assert_eq!(game_ids(&PtnTarSource::new(&tar_path)), ids);
+
+    // The last file is only read now
+    let filter = GameFilter {
+        board_size: 5,
+        min_game_id: 0,
+    };
+    let mut games = source.road_wins(&filter).unwrap();
+    assert!(games.next().unwrap().is_err());
+    let game = games.next().unwrap().unwrap();
+    assert!(game.source.starts_with("ptn:"));
+    assert!(games.next().unwrap().is_ok());
+    fs::remove_file(dir.join("b").join("c.ptn")).unwrap();
+    assert!(matches!(
+        games.next(),
+        Some(Err(TinueFinderError::InvalidPtn(_)))
+    ));
+    assert!(games.next().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_file(zip_path).unwrap();
+    fs::remove_file(tar_path).unwrap();
+}
+
+#[test]
+fn sqlite_source_failed_page_test() {
+    let path = std::env::temp_dir().join(format!("tinue-finder-games-{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let conn = Connection::open(path).unwrap();
+    conn.execute(
+        "CREATE TABLE games (id integer primary key, notation TEXT, result TEXT, size integer, player_white TEXT, player_black TEXT)",
+        params![],
+    )
+    .unwrap();
+    // One more than the games of a page
+    for id in 1..=1001 {
+        conn.execute(
+            "INSERT INTO games VALUES(?, '', 'R-0', 5, NULL, NULL)",
+            params![id],
+        )
+        .unwrap();
+    }
+
+    let source = SqliteGameSource::open(path).unwrap();
+    let filter = GameFilter {
+        board_size: 5,
+        min_game_id: 0,
+    };
+    let mut games = source.road_wins(&filter).unwrap();
+    assert_eq!(
+        games.by_ref().take(1000).filter(Result::is_ok).count(),
+        1000
+    );
+
+    // The query of the second page fails
+    conn.execute("DROP TABLE games", params![]).unwrap();
+    assert!(matches!(
+        games.next(),
+        Some(Err(TinueFinderError::SourceInterrupted {
+            next_id: 1001,
+            ..
+        }))
+    ));
+    assert!(games.next().is_none());
+
+    drop(games);
+    drop(source);
+    fs::remove_file(path).unwrap();
+}
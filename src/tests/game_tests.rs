@@ -0,0 +1,43 @@
+use crate::game::scan_plies;
+use crate::tests::tinue_game_with_last_move;
+use crate::{SearchLimits, Solver};
+use board_game_traits::Color;
+
+#[test]
+fn scan_plies_tinue_test() {
+    for solver in [Solver::Iddfs, Solver::Pns].iter() {
+        let (results, _stats) = scan_plies::<5>(
+            &tinue_game_with_last_move("2c5>11"),
+            3,
+            true,
+            *solver,
+            &SearchLimits::unlimited(),
+        )
+        .unwrap();
+        assert_eq!(results.len(), 21);
+
+        let last = &results[20];
+        assert_eq!(last.ply, 20);
+        assert_eq!(last.side_to_move, Color::White);
+        assert_eq!(last.played_move, "2c5>11");
+        assert_eq!(last.tinue.as_ref().map(|tinue| tinue.depth), Some(3));
+        assert!(!last.missed_tinue);
+    }
+}
+
+#[test]
+fn scan_plies_missed_tinue_test() {
+    let (results, _stats) = scan_plies::<5>(
+        &tinue_game_with_last_move("a2"),
+        3,
+        true,
+        Solver::Iddfs,
+        &SearchLimits::unlimited(),
+    )
+    .unwrap();
+
+    let last = &results[20];
+    assert_eq!(last.played_move, "a2");
+    assert!(last.tinue.is_some());
+    assert!(last.missed_tinue);
+}
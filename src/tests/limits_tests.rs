@@ -0,0 +1,72 @@
+use crate::game::do_it;
+use crate::tests::{tinue_game_with_last_move, tinue_position};
+use crate::{
+    tinue_search_from, SearchAborted, SearchBudget, SearchLimits, Solver, TinueFinderError,
+};
+use board_game_traits::{Color, Position as PositionTrait};
+use pgn_traits::PgnPosition;
+use std::sync::atomic::Ordering;
+use tiltak::position::Position;
+
+/// There is no Tinue within 5 plies, but disproving it takes far more nodes than a search
+/// checks the cancel flag after
+fn opening_position() -> Position<5> {
+    let mut position = Position::start_position();
+    for mv in ["a1", "e5", "c3", "c2"].iter() {
+        let mv = position.move_from_san(mv).unwrap();
+        position.do_move(mv);
+    }
+    position
+}
+
+#[test]
+fn node_limit_test() {
+    let limits = SearchLimits {
+        max_nodes: Some(10),
+        ..SearchLimits::unlimited()
+    };
+    for solver in [Solver::Iddfs, Solver::Pns].iter() {
+        let mut position = tinue_position();
+        let mut budget = SearchBudget::new(&limits);
+        let result = tinue_search_from(
+            *solver,
+            &mut position,
+            1,
+            3,
+            Color::White,
+            true,
+            &mut budget,
+        );
+        assert_eq!(result.err(), Some(SearchAborted::NodeLimit));
+        assert_eq!(position.to_fen(), tinue_position().to_fen());
+    }
+
+    // Reported as an error of the game rather than as a game without Tinue
+    let moves = tinue_game_with_last_move("2c5>11");
+    let result = do_it::<5>(&moves, 1, 1, 3, true, Solver::Iddfs, &limits);
+    assert!(matches!(
+        result,
+        Err(TinueFinderError::SearchAborted(SearchAborted::NodeLimit))
+    ));
+}
+
+#[test]
+fn cancel_test() {
+    let limits = SearchLimits::unlimited();
+    limits.cancel.store(true, Ordering::SeqCst);
+    for solver in [Solver::Iddfs, Solver::Pns].iter() {
+        let mut position = opening_position();
+        let mut budget = SearchBudget::new(&limits);
+        let result = tinue_search_from(
+            *solver,
+            &mut position,
+            1,
+            5,
+            Color::White,
+            true,
+            &mut budget,
+        );
+        assert_eq!(result.err(), Some(SearchAborted::Cancelled));
+        assert_eq!(position.to_fen(), opening_position().to_fen());
+    }
+}
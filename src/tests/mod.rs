@@ -1,10 +1,57 @@
-use crate::iddf_tinue_search;
+use crate::game::GameMoves;
+use crate::pns::pns_tinue_search;
+use crate::{iddf_tinue_search, TinueMove};
 use board_game_traits::Position as PositionTrait;
 use pgn_traits::PgnPosition;
 use tiltak::position::{Move, Position};
 
+mod compact_tests;
+mod db_tests;
+mod defense_tests;
+mod dot_tests;
+mod game_source_tests;
+mod game_tests;
+mod limits_tests;
+mod playtak_tests;
+mod pns_tests;
+mod ptn_tests;
+mod serialize_tests;
+mod stats_tests;
+mod symmetry_tests;
 mod tinue_tests_5s;
 mod tinue_tests_6s;
+mod tps_tests;
+mod transposition_tests;
+
+fn tinue_move(mv: &str, next: Option<Vec<TinueMove>>) -> TinueMove {
+    TinueMove {
+        mv: mv.to_string(),
+        next,
+    }
+}
+
+/// The 5s game of `tinue_tests_5s::tinue_test`, after which white has a Tinue of 3 plies with `2c5>11`
+const TINUE_GAME: [&str; 20] = [
+    "a1", "a5", "b5", "Cc3", "c5", "d5", "Cd4", "c4", "e5", "1c4+1", "1d4+1", "c4", "1d5<1",
+    "1d5>1", "d5", "e4", "2c5>11", "1d5<1", "2e5<11", "2d5>2",
+];
+
+/// `TINUE_GAME` continued with `last_move`
+fn tinue_game_with_last_move(last_move: &str) -> GameMoves {
+    let mut moves: Vec<String> = TINUE_GAME.iter().map(|mv| mv.to_string()).collect();
+    moves.push(last_move.to_string());
+    GameMoves::Ptn(moves)
+}
+
+/// The position after `TINUE_GAME`
+fn tinue_position() -> Position<5> {
+    let mut position = Position::start_position();
+    for mv in TINUE_GAME.iter() {
+        let mv = position.move_from_san(mv).unwrap();
+        position.do_move(mv);
+    }
+    position
+}
 
 // Runs a tinue test with a single solution
 fn run_tinue_test<const S: usize>(depth: u32, move_strings: &[&str], answer_move_string: &str) {
@@ -32,5 +79,14 @@ fn run_tinue_test<const S: usize>(depth: u32, move_strings: &[&str], answer_move
     // Check that the tinue solution is correct and unique
     let result = iddf_tinue_search(&mut position, depth, side_to_move, false).unwrap();
     assert_eq!(result.result.len(), 1);
+    assert_eq!(result.result[0].mv, answer_move.to_string::<S>());
+
+    // Cross-check with proof-number search
+    let shallow_depth_result = pns_tinue_search(&mut position, depth - 2, side_to_move, false);
+    assert!(shallow_depth_result.is_none());
+
+    let result = pns_tinue_search(&mut position, depth, side_to_move, false).unwrap();
+    assert_eq!(result.depth, depth);
+    assert_eq!(result.result.len(), 1);
     assert_eq!(result.result[0].mv, answer_move.to_string::<S>())
 }
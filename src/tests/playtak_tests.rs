@@ -0,0 +1,25 @@
+use crate::playtak::parse_server_notation;
+use pgn_traits::PgnPosition;
+use tiltak::position::Position;
+
+#[test]
+fn parse_server_notation_test() {
+    let moves = parse_server_notation::<5>("P A1,P E5 C,P B2 W,M A1 A3 1 2,M E5 C5 1 1").unwrap();
+    let position = Position::<5>::start_position();
+    let ptn: Vec<String> = moves.iter().map(|mv| position.move_to_san(mv)).collect();
+
+    // `move_to_san` may omit the piece count and drops of single pieces
+    let expected: Vec<String> = ["a1", "Ce5", "Sb2", "3a1+12", "2e5<11"]
+        .iter()
+        .map(|mv| position.move_to_san(&position.move_from_san(mv).unwrap()))
+        .collect();
+    assert_eq!(ptn, expected);
+}
+
+#[test]
+fn parse_malformed_server_notation_test() {
+    assert!(parse_server_notation::<5>("P A1,P F1").is_err());
+    assert!(parse_server_notation::<5>("P A1,M A1 B2 1").is_err());
+    assert!(parse_server_notation::<5>("P A1,M A1 A3 1").is_err());
+    assert!(parse_server_notation::<5>("P A1,X").is_err());
+}
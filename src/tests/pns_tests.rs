@@ -0,0 +1,41 @@
+use crate::iddf_tinue_search;
+use crate::limits::{SearchAborted, SearchBudget, SearchLimits};
+use crate::pns::{pns_tinue_search, pns_tinue_search_limited};
+use crate::tests::tinue_position;
+use board_game_traits::Position as PositionTrait;
+use pgn_traits::PgnPosition;
+
+#[test]
+fn pns_depth_zero_test() {
+    let mut position = tinue_position();
+    let me = position.side_to_move();
+    let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+    let result = pns_tinue_search_limited(&mut position, 0, me, true, &mut budget).unwrap();
+    assert!(result.is_none());
+}
+
+#[test]
+fn pns_memory_limit_test() {
+    let mut position = tinue_position();
+    let me = position.side_to_move();
+    let limits = SearchLimits {
+        max_tree_nodes: Some(10),
+        ..SearchLimits::unlimited()
+    };
+    let mut budget = SearchBudget::new(&limits);
+    let result = pns_tinue_search_limited(&mut position, 3, me, true, &mut budget);
+    assert_eq!(result.err(), Some(SearchAborted::MemoryLimit));
+    // The position is restored after the search is aborted
+    assert_eq!(position.to_fen(), tinue_position().to_fen());
+}
+
+#[test]
+fn pns_shortest_depth_test() {
+    let mut position = tinue_position();
+    let me = position.side_to_move();
+    // The proof at depth 7 may have longer lines, the reported depth is the same as with IDDFS
+    let pns = pns_tinue_search(&mut position, 7, me, true).unwrap();
+    let iddfs = iddf_tinue_search(&mut position, 7, me, true).unwrap();
+    assert_eq!(pns.depth, 3);
+    assert_eq!(pns.depth, iddfs.depth);
+}
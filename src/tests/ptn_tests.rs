@@ -0,0 +1,62 @@
+use crate::ptn::{annotated_move_text, annotated_ptn, parse_ptn_games, tinue_line};
+use crate::tests::tinue_move;
+
+#[test]
+fn parse_ptn_games_test() {
+    let text = r#"[Size "5"]
+[Player1 "Jan"]
+[Result "R-0"]
+
+1. a1 e5 {opening}
+2. Ce4' 2e5<11!
+3. a2 -- R-0
+
+[Size "6"]
+[Komi "2"]
+
+1. f6 a1 0-1
+"#;
+    let games = parse_ptn_games(text);
+    assert_eq!(games.len(), 2);
+
+    assert_eq!(games[0].tag("size"), Some("5"));
+    assert_eq!(games[0].tag("Player1"), Some("Jan"));
+    assert_eq!(games[0].tag("Result"), Some("R-0"));
+    assert_eq!(games[0].moves, ["a1", "e5", "Ce4", "2e5<11", "a2"]);
+
+    assert_eq!(games[1].tag("Komi"), Some("2"));
+    assert_eq!(games[1].tag("Player1"), None);
+    assert_eq!(games[1].moves, ["f6", "a1"]);
+}
+
+#[test]
+fn annotated_tinue_test() {
+    let tinue = vec![tinue_move(
+        "a1",
+        Some(vec![
+            tinue_move("b1", Some(vec![tinue_move("c1", None)])),
+            tinue_move(
+                "b2",
+                Some(vec![tinue_move(
+                    "c2",
+                    Some(vec![tinue_move("d2", Some(vec![tinue_move("e2", None)]))]),
+                )]),
+            ),
+        ]),
+    )];
+    let line = tinue_line(&tinue);
+    assert_eq!(
+        annotated_move_text(3, true, &line),
+        "3. a1 b2 (3... b1 4. c1)\n4. c2 d2\n5. e2"
+    );
+    assert_eq!(
+        annotated_move_text(3, false, &line[..3]),
+        "3... a1\n4. b2 (4. b1 4... c1) 4... c2"
+    );
+
+    // Viewers that don't know variations still read the main line
+    let ptn = annotated_ptn(&[("Size", "5".to_string())], &line);
+    let games = parse_ptn_games(&ptn);
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].moves, ["a1", "b2", "c2", "d2", "e2"]);
+}
@@ -0,0 +1,91 @@
+use crate::serialize::{
+    options_to_tinuemoves, parse_tinue_json, tinue_to_json, tinuemove_to_options, TinueMoveOptions,
+    TinueSolution, VersionedTinue, TINUE_FORMAT_VERSION,
+};
+use crate::tests::tinue_move;
+use crate::{IDDFSResult, TinueFinderError, TinueMove};
+
+fn tinue() -> IDDFSResult<Vec<TinueMove>> {
+    IDDFSResult {
+        depth: 3,
+        result: vec![tinue_move(
+            "a1",
+            Some(vec![tinue_move("b1", Some(vec![tinue_move("c1", None)]))]),
+        )],
+    }
+}
+
+#[test]
+fn tinue_json_round_trip_test() {
+    let line = tinue_to_json(Some(&tinue()), true).unwrap();
+    assert_eq!(
+        line,
+        r#"{"format_version":1,"kind":"line","moves":["a1","b1","c1"]}"#
+    );
+    assert_eq!(
+        parse_tinue_json(&line).unwrap(),
+        Some(VersionedTinue::new(TinueSolution::Line {
+            moves: vec!["a1".to_string(), "b1".to_string(), "c1".to_string()]
+        }))
+    );
+
+    let options = tinue_to_json(Some(&tinue()), false).unwrap();
+    let parsed = parse_tinue_json(&options).unwrap().unwrap();
+    assert_eq!(parsed.format_version, TINUE_FORMAT_VERSION);
+    match parsed.tinue {
+        TinueSolution::Options { options } => {
+            assert_eq!(options[0].moves, ["a1"]);
+            assert_eq!(options[0].solutions[0].solutions[0].moves, ["c1"]);
+        }
+        TinueSolution::Line { .. } => panic!("expected options"),
+    }
+
+    assert_eq!(
+        parse_tinue_json(&tinue_to_json(None, true).unwrap()).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn parse_legacy_tinue_json_test() {
+    assert_eq!(
+        parse_tinue_json(r#"["a1","b1","c1"]"#).unwrap(),
+        Some(VersionedTinue::new(TinueSolution::Line {
+            moves: vec!["a1".to_string(), "b1".to_string(), "c1".to_string()]
+        }))
+    );
+    assert_eq!(
+        parse_tinue_json(r#"[{"moves":["a1"],"solutions":[{"moves":["b1","b2"]}]}]"#).unwrap(),
+        Some(VersionedTinue::new(TinueSolution::Options {
+            options: vec![TinueMoveOptions {
+                moves: vec!["a1".to_string()],
+                solutions: vec![TinueMoveOptions {
+                    moves: vec!["b1".to_string(), "b2".to_string()],
+                    solutions: vec![],
+                }],
+            }]
+        }))
+    );
+    assert!(matches!(
+        parse_tinue_json(r#"{"format_version":99,"kind":"line","moves":[]}"#),
+        Err(TinueFinderError::UnsupportedFormatVersion(99))
+    ));
+}
+
+#[test]
+fn options_to_tinuemoves_test() {
+    let tree = vec![tinue_move(
+        "a1",
+        Some(vec![
+            tinue_move("b1", Some(vec![tinue_move("c1", None)])),
+            tinue_move("b2", Some(vec![tinue_move("c1", None)])),
+        ]),
+    )];
+    let options = tinuemove_to_options(&tree);
+    assert_eq!(options[0].solutions[0].moves, ["b1", "b2"]);
+    assert_eq!(options_to_tinuemoves(&options), tree);
+    assert_eq!(
+        options_to_tinuemoves(&tinuemove_to_options(&tinue().result)),
+        tinue().result
+    );
+}
@@ -0,0 +1,52 @@
+use crate::stats::SearchStats;
+use std::time::Duration;
+
+#[test]
+fn iterations_are_merged_by_depth_test() {
+    let mut stats = SearchStats::default();
+    stats.record_iteration(3, 100, Duration::from_millis(10));
+    stats.record_iteration(1, 5, Duration::from_millis(1));
+    stats.record_iteration(3, 50, Duration::from_millis(5));
+
+    let depths: Vec<u32> = stats.iterations.iter().map(|i| i.depth).collect();
+    assert_eq!(depths, vec![1, 3]);
+    assert_eq!(stats.iterations[1].searches, 2);
+    assert_eq!(stats.iterations[1].nodes, 150);
+    assert_eq!(stats.iterations[1].time, Duration::from_millis(15));
+}
+
+#[test]
+fn derived_stats_test() {
+    let mut stats = SearchStats::default();
+    assert_eq!(stats.or_branching_factor(), 0.0);
+    assert_eq!(stats.tt_hit_rate(), None);
+
+    stats.record_node(true);
+    stats.record_children(true, 4);
+    stats.record_node(true);
+    stats.record_children(true, 2);
+    stats.record_node(false);
+    stats.record_children(false, 5);
+    stats.record_tt_lookup(true);
+    stats.record_tt_lookup(false);
+
+    assert_eq!(stats.or_branching_factor(), 3.0);
+    assert_eq!(stats.and_branching_factor(), 5.0);
+    assert_eq!(stats.tt_hit_rate(), Some(0.5));
+}
+
+#[test]
+fn serialize_stats_test() {
+    let mut stats = SearchStats {
+        nodes: 105,
+        time: Duration::from_millis(12),
+        ..SearchStats::default()
+    };
+    stats.record_iteration(1, 5, Duration::from_millis(1));
+
+    let json = serde_json::to_value(&stats).unwrap();
+    assert_eq!(json["nodes"], 105);
+    assert_eq!(json["timeMs"], 12);
+    assert_eq!(json["ttLookups"], 0);
+    assert_eq!(json["iterations"][0]["timeMs"], 1);
+}
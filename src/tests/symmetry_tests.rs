@@ -0,0 +1,171 @@
+use crate::search::iddf_tinue_search_with_pruning;
+use crate::symmetry::{
+    canonical_key, invariant_symmetries, transform_move, Symmetry, SymmetrySet, TpsBoard,
+};
+use crate::tps::position_from_tps;
+use crate::{Mov, SearchBudget, SearchLimits, TinueMove};
+use board_game_traits::Color;
+use std::collections::HashSet;
+use tiltak::position::Position;
+
+#[test]
+fn symmetries_are_distinct_test() {
+    let board = TpsBoard::parse("1,x4/x5/x5/x5/x4,2C 1 4").unwrap();
+    let mut corners: Vec<(usize, usize)> = Symmetry::all()
+        .map(|symmetry| symmetry.map_square(1, 0, 5))
+        .collect();
+    corners.sort_unstable();
+    corners.dedup();
+    assert_eq!(corners.len(), 8);
+
+    assert_eq!(board.transform(Symmetry::IDENTITY, false), board);
+}
+
+#[test]
+fn tps_key_round_trip_test() {
+    let tps = "x2,2,x2/x,12S,x3/x5/x3,21C,x/1,x4 2 12";
+    let board = TpsBoard::parse(tps).unwrap();
+    assert_eq!(board.to_tps_key(), "x2,2,x2/x,12S,x3/x5/x3,21C,x/1,x4 2");
+}
+
+#[test]
+fn canonical_key_test() {
+    // The same position, rotated by 180 degrees
+    let a = canonical_key("2,1,x3/x5/x5/x5/x5 1 5").unwrap();
+    let b = canonical_key("x5/x5/x5/x5/x3,1,2 1 9").unwrap();
+    assert_eq!(a, b);
+
+    // The same position with swapped colours
+    let c = canonical_key("1,2,x3/x5/x5/x5/x5 2 5").unwrap();
+    assert_eq!(a, c);
+
+    // A different player to move is a different position
+    let d = canonical_key("2,1,x3/x5/x5/x5/x5 2 5").unwrap();
+    assert_ne!(a, d);
+}
+
+#[test]
+fn transform_move_test() {
+    let mirrored = Symmetry {
+        mirrored: true,
+        rotations: 0,
+    };
+    assert_eq!(transform_move("Ca1", mirrored, 5), "Ce1");
+    assert_eq!(transform_move("3b2>12", mirrored, 5), "3d2<12");
+    assert_eq!(transform_move("c3+", mirrored, 5), "c3+");
+
+    // Every quarter turn maps a square and its direction the same way
+    let rotated = Symmetry {
+        mirrored: false,
+        rotations: 1,
+    };
+    let (file, rank) = rotated.map_square(0, 0, 5);
+    let (next_file, next_rank) = rotated.map_square(0, 1, 5);
+    let square = format!("{}{}", (b'a' + file as u8) as char, rank + 1);
+    let direction = match (
+        next_file as i32 - file as i32,
+        next_rank as i32 - rank as i32,
+    ) {
+        (0, 1) => '+',
+        (0, -1) => '-',
+        (1, 0) => '>',
+        _ => '<',
+    };
+    assert_eq!(
+        transform_move("2a1+11", rotated, 5),
+        format!("2{}{}11", square, direction)
+    );
+}
+
+#[test]
+fn invariant_symmetries_test() {
+    let mirrored = Symmetry {
+        mirrored: true,
+        rotations: 0,
+    };
+    let position: Position<5> = position_from_tps(SYMMETRIC_TINUE_TPS, None).unwrap();
+    let symmetries = invariant_symmetries(&position);
+    assert_eq!(symmetries.iter().collect::<Vec<_>>(), vec![mirrored]);
+
+    let position: Position<5> = position_from_tps("x5/x5/x5/x5/x5 1 1", None).unwrap();
+    assert_eq!(invariant_symmetries(&position).iter().count(), 7);
+
+    // Only the reflection in the diagonal through a5 keeps the stone in place
+    let position: Position<5> = position_from_tps("1,x4/x5/x5/x5/x5 1 2", None).unwrap();
+    assert_eq!(
+        invariant_symmetries(&position),
+        [Symmetry {
+            mirrored: true,
+            rotations: 3,
+        }]
+        .iter()
+        .copied()
+        .collect::<SymmetrySet>()
+    );
+
+    // The same symmetries as those of the TPS
+    for tps in [
+        "1,x4/x5/x5/x5/x5 1 2",
+        "x5/x5/x5/x5/1,x4 1 2",
+        "x4,2/x5/x2,1,x2/x5/2,x4 1 3",
+    ]
+    .iter()
+    {
+        let board = TpsBoard::parse(tps).unwrap();
+        let position: Position<5> = position_from_tps(tps, None).unwrap();
+        let expected = Symmetry::all()
+            .skip(1)
+            .filter(|&symmetry| board.is_invariant(symmetry))
+            .collect::<SymmetrySet>();
+        assert_eq!(invariant_symmetries(&position), expected);
+    }
+}
+
+/// Every line of the tree from the first move to a road, which doesn't depend on the order of the moves
+fn lines(tinue_moves: &[TinueMove]) -> HashSet<Vec<Mov>> {
+    let mut result = HashSet::new();
+    for tm in tinue_moves {
+        match &tm.next {
+            Some(next) if !next.is_empty() => {
+                for mut line in lines(next) {
+                    line.insert(0, tm.mv.clone());
+                    result.insert(line);
+                }
+            }
+            _ => {
+                result.insert(vec![tm.mv.clone()]);
+            }
+        }
+    }
+    result
+}
+
+/// Symmetric to the c file. White wins with c5, which threatens b4, d4 and c3.
+const SYMMETRIC_TINUE_TPS: &str = "2,x3,2/x2,1,x2/x,1,x,1,x/x,1,x,1,x/x,1,x,1,x 1 10";
+
+#[test]
+fn symmetry_pruning_test() {
+    let mut position: Position<5> = position_from_tps(SYMMETRIC_TINUE_TPS, None).unwrap();
+    let mut search = |prune_symmetries| {
+        let mut budget = SearchBudget::new(&SearchLimits::unlimited());
+        let result = iddf_tinue_search_with_pruning(
+            &mut position,
+            1,
+            3,
+            Color::White,
+            false,
+            prune_symmetries,
+            &mut budget,
+        )
+        .unwrap()
+        .unwrap();
+        (result, budget.nodes())
+    };
+    let (pruned, pruned_nodes) = search(true);
+    let (unpruned, unpruned_nodes) = search(false);
+
+    assert_eq!(pruned.depth, 3);
+    assert_eq!(unpruned.depth, 3);
+    assert_eq!(lines(&pruned.result), lines(&unpruned.result));
+    assert!(pruned_nodes < unpruned_nodes);
+}
@@ -0,0 +1,56 @@
+use crate::tests::tinue_position;
+use crate::tps::{solve_tps, with_side_to_move};
+use crate::{Solver, TinueFinderError};
+use board_game_traits::Color;
+use pgn_traits::PgnPosition;
+
+/// White threatens roads on a1 and c1, black has a road with b1
+const ROAD_THREATS_TPS: &str = "1,2,1/1,2,1/x3 2 4";
+
+#[test]
+fn with_side_to_move_test() {
+    assert_eq!(
+        with_side_to_move(ROAD_THREATS_TPS, Color::White).unwrap(),
+        "1,2,1/1,2,1/x3 1 4"
+    );
+    assert_eq!(
+        with_side_to_move(ROAD_THREATS_TPS, Color::Black).unwrap(),
+        ROAD_THREATS_TPS
+    );
+    assert!(matches!(
+        with_side_to_move("1,2,1/1,2,1/x3 2", Color::White),
+        Err(TinueFinderError::InvalidTps { .. })
+    ));
+}
+
+#[test]
+fn solve_tps_side_to_move_test() {
+    for solver in [Solver::Iddfs, Solver::Pns].iter() {
+        let black = solve_tps::<3>(ROAD_THREATS_TPS, None, 1, false, *solver)
+            .unwrap()
+            .unwrap();
+        assert_eq!(black.depth, 1);
+        let black_moves: Vec<&str> = black.result.iter().map(|m| m.mv.as_str()).collect();
+        assert_eq!(black_moves, vec!["b1"]);
+
+        let white = solve_tps::<3>(ROAD_THREATS_TPS, Some(Color::White), 1, false, *solver)
+            .unwrap()
+            .unwrap();
+        assert_eq!(white.depth, 1);
+        let mut white_moves: Vec<&str> = white.result.iter().map(|m| m.mv.as_str()).collect();
+        white_moves.sort_unstable();
+        assert_eq!(white_moves, vec!["a1", "c1"]);
+    }
+}
+
+#[test]
+fn solve_tps_test() {
+    // The position of `tinue_tests_5s::tinue_test`
+    let tps = tinue_position().to_fen();
+
+    let result = solve_tps::<5>(&tps, None, 3, true, Solver::Iddfs)
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.depth, 3);
+    assert_eq!(result.result[0].mv, "2c5>11");
+}
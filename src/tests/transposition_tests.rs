@@ -0,0 +1,58 @@
+use crate::transposition_table::{position_key, PositionKey, TranspositionTable};
+use board_game_traits::Position as PositionTrait;
+use pgn_traits::PgnPosition;
+use tiltak::position::Position;
+
+fn play(moves: &[&str]) -> Position<5> {
+    let mut position = Position::start_position();
+    for mv in moves {
+        let mv = position.move_from_san(mv).unwrap();
+        position.do_move(mv);
+    }
+    position
+}
+
+#[test]
+fn position_key_transposition_test() {
+    let a = play(&["a1", "e5", "b1", "d5", "c1", "c5"]);
+    let b = play(&["a1", "e5", "c1", "c5", "b1", "d5"]);
+    assert_eq!(position_key(&a), position_key(&b));
+
+    let c = play(&["a1", "e5", "b1", "d5", "c1", "b5"]);
+    assert_ne!(position_key(&a), position_key(&c));
+}
+
+fn key(hash: u64, check: u64) -> PositionKey {
+    PositionKey { hash, check }
+}
+
+#[test]
+fn proven_no_win_test() {
+    let mut tt = TranspositionTable::new();
+    assert!(!tt.is_proven_no_win(key(1, 1), 1));
+
+    tt.store_no_win(key(1, 1), 3);
+    tt.store_no_win(key(1, 1), 1);
+    assert!(tt.is_proven_no_win(key(1, 1), 1));
+    assert!(tt.is_proven_no_win(key(1, 1), 3));
+    assert!(!tt.is_proven_no_win(key(1, 1), 5));
+    assert!(!tt.is_proven_no_win(key(2, 1), 1));
+    // A different position with the same zobrist hash
+    assert!(!tt.is_proven_no_win(key(1, 2), 1));
+}
+
+#[test]
+fn proven_win_test() {
+    let position = play(&["a1", "e5"]);
+    let a2 = position.move_from_san("a2").unwrap();
+    let b1 = position.move_from_san("b1").unwrap();
+
+    let mut tt = TranspositionTable::new();
+    tt.store_win(key(1, 1), 5, Some(a2.clone()));
+    tt.store_win(key(1, 1), 3, Some(b1.clone()));
+    tt.store_win(key(1, 1), 7, Some(a2));
+    let entry = tt.get(key(1, 1)).unwrap();
+    assert_eq!(entry.win_depth, Some(3));
+    assert_eq!(entry.best_move, Some(b1));
+    assert!(tt.get(key(1, 2)).is_none());
+}
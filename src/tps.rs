@@ -0,0 +1,84 @@
+use board_game_traits::{Color, Position as PositionTrait};
+use pgn_traits::PgnPosition;
+use tiltak::position::Position;
+
+use crate::game::dispatch_board_size;
+use crate::{tinue_search, IDDFSResult, Solver, TinueFinderError, TinueMove};
+
+/// Returns the board size of a TPS string, i.e. its number of rows
+pub fn tps_board_size(tps: &str) -> Result<u32, TinueFinderError> {
+    let board = tps
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| invalid_tps(tps, "empty"))?;
+    Ok(board.split('/').count() as u32)
+}
+
+/// Parses `tps`. If `side_to_move` is given, it replaces the player to move of the TPS.
+pub fn position_from_tps<const S: usize>(
+    tps: &str,
+    side_to_move: Option<Color>,
+) -> Result<Position<S>, TinueFinderError> {
+    let tps = match side_to_move {
+        Some(color) => with_side_to_move(tps, color)?,
+        None => tps.to_string(),
+    };
+    Position::<S>::from_fen(&tps).map_err(|err| invalid_tps(&tps, &err.to_string()))
+}
+
+/// Replaces the player to move of `tps`
+pub fn with_side_to_move(tps: &str, color: Color) -> Result<String, TinueFinderError> {
+    let mut fields: Vec<&str> = tps.split_whitespace().collect();
+    if fields.len() != 3 {
+        return Err(invalid_tps(
+            tps,
+            "expected board, player to move and move number",
+        ));
+    }
+    fields[1] = match color {
+        Color::White => "1",
+        Color::Black => "2",
+    };
+    Ok(fields.join(" "))
+}
+
+/// Searches the position given by `tps` for a Tinue of the side to move
+pub fn solve_tps<const S: usize>(
+    tps: &str,
+    side_to_move: Option<Color>,
+    depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+) -> Result<Option<IDDFSResult<Vec<TinueMove>>>, TinueFinderError> {
+    let mut position = position_from_tps::<S>(tps, side_to_move)?;
+    let me = position.side_to_move();
+
+    Ok(tinue_search(
+        solver,
+        &mut position,
+        depth,
+        me,
+        find_only_one_tinue,
+    ))
+}
+
+/// Calls `solve_tps` with the const generic matching the board size of `tps`
+pub fn solve_tps_sized(
+    tps: &str,
+    side_to_move: Option<Color>,
+    depth: u32,
+    find_only_one_tinue: bool,
+    solver: Solver,
+) -> Result<Option<IDDFSResult<Vec<TinueMove>>>, TinueFinderError> {
+    dispatch_board_size!(
+        tps_board_size(tps)?,
+        solve_tps(tps, side_to_move, depth, find_only_one_tinue, solver)
+    )
+}
+
+fn invalid_tps(tps: &str, reason: &str) -> TinueFinderError {
+    TinueFinderError::InvalidTps {
+        tps: tps.to_string(),
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use tiltak::position::{Move, Position, Square};
+
+use crate::symmetry::SymmetrySet;
+
+/// What is known about a single position for the player searching for a Tinue.
+///
+/// The moves of a **Road to Tinue** have to be returned, so a position with a Tinue is searched again.
+/// Its first move is stored though and searched first the next time.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TranspositionEntry {
+    /// Smallest depth at which a **Road to Tinue** was found
+    pub win_depth: Option<u32>,
+    /// First move of the **Road to Tinue** of `win_depth`, only stored if `me` is to move
+    pub best_move: Option<Move>,
+    /// Greatest depth at which it was proven that there is no **Road to Tinue**
+    pub no_win_depth: Option<u32>,
+    /// Symmetries that map the position onto itself, see `symmetry::invariant_symmetries`
+    pub symmetries: Option<SymmetrySet>,
+}
+
+/// Identifies a position in the `TranspositionTable`, see `position_key`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    /// The zobrist hash of the position
+    pub hash: u64,
+    /// A second hash of the board, so that a collision of `hash` alone cannot return the entry of another position
+    pub check: u64,
+}
+
+/// Caches the results of `win_in_n` so that positions reached through
+/// different move orders or in earlier IDDFS iterations are not searched again.
+///
+/// The table is only valid for a single search, i.e. for one player `me`
+/// and one setting of `find_only_one_tinue`.
+#[derive(Debug, Default)]
+pub struct TranspositionTable {
+    entries: HashMap<PositionKey, TranspositionEntry>,
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: PositionKey) -> Option<&TranspositionEntry> {
+        self.entries.get(&key)
+    }
+
+    /// Returns `true` if it is already known that there is no Tinue within `depth` plies
+    pub fn is_proven_no_win(&self, key: PositionKey, depth: u32) -> bool {
+        matches!(self.get(key), Some(TranspositionEntry { no_win_depth: Some(d), .. }) if *d >= depth)
+    }
+
+    /// Remembers that there is no Tinue within `depth` plies
+    pub fn store_no_win(&mut self, key: PositionKey, depth: u32) {
+        let entry = self.entries.entry(key).or_default();
+        entry.no_win_depth = Some(entry.no_win_depth.map_or(depth, |d| d.max(depth)));
+    }
+
+    /// Remembers that there is a Tinue of length `depth`, starting with `best_move` if `me` is to move
+    pub fn store_win(&mut self, key: PositionKey, depth: u32, best_move: Option<Move>) {
+        let entry = self.entries.entry(key).or_default();
+        if entry.win_depth.map_or(true, |d| depth <= d) {
+            entry.win_depth = Some(depth);
+            entry.best_move = best_move;
+        }
+    }
+
+    /// Returns the symmetries of the position, `compute` is only called the first time
+    pub fn symmetries(
+        &mut self,
+        key: PositionKey,
+        compute: impl FnOnce() -> SymmetrySet,
+    ) -> SymmetrySet {
+        let entry = self.entries.entry(key).or_default();
+        *entry.symmetries.get_or_insert_with(compute)
+    }
+}
+
+/// Hashes the position independently of the moves that led to it.
+///
+/// Uses the zobrist hash that tiltak updates incrementally with every move. It covers the board
+/// and the side to move, but not the move number, so positions reached via transpositions map to the same key.
+/// The check is hashed from the stacks of the board.
+pub fn position_key<const S: usize>(position: &Position<S>) -> PositionKey {
+    let mut hasher = DefaultHasher::new();
+    for square in 0..S * S {
+        position[Square(square as u8)].hash(&mut hasher);
+    }
+    PositionKey {
+        hash: position.zobrist_hash(),
+        check: hasher.finish(),
+    }
+}